@@ -0,0 +1,128 @@
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+#[cfg(test)]
+use super::spin_lock_guard::SpinLock;
+#[cfg(test)]
+use std::thread;
+
+//
+// A backoff strategy decides what to do in between two failed attempts to take a lock. A fresh instance is created
+//  (using Default) for every acquisition, so a strategy can keep track of how long it has been waiting so far.
+//
+// Rules of thumb:
+//   - NoBackoff is best for very short critical sections with only a couple of contending threads.
+//   - ExponentialSpin reduces the cache line traffic on the lock state when more threads contend.
+//   - SpinThenYield/SpinThenSleep give up the core after a while, which matters once there are more contending threads
+//      than cores, or when the lock holder may be descheduled. They need the 'std' feature.
+//
+pub trait Backoff: Default {
+    fn backoff(&mut self);
+}
+
+// The number of exponential spin steps, i.e. the longest spin phase is 2^SPIN_LIMIT spin loop hints.
+const SPIN_LIMIT: u32 = 6;
+
+// The number of exponential sleep steps, i.e. the longest sleep is 2^SLEEP_LIMIT microseconds.
+#[cfg(feature = "std")]
+const SLEEP_LIMIT: u32 = 10;
+
+fn spin(step: u32) {
+    for _ in 0..1 << step {
+        core::hint::spin_loop();
+    }
+}
+
+/// A single spin loop hint in between attempts, i.e. no backoff at all.
+#[derive(Default)]
+pub struct NoBackoff;
+
+impl Backoff for NoBackoff {
+    fn backoff(&mut self) {
+        core::hint::spin_loop();
+    }
+}
+
+/// Doubles the number of spin loop hints in between attempts, up to a limit.
+#[derive(Default)]
+pub struct ExponentialSpin {
+    step: u32,
+}
+
+impl Backoff for ExponentialSpin {
+    fn backoff(&mut self) {
+        spin(self.step);
+
+        if self.step < SPIN_LIMIT {
+            self.step += 1;
+        }
+    }
+}
+
+/// Spins exponentially for a while, then yields to the OS scheduler in between attempts.
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub struct SpinThenYield {
+    step: u32,
+}
+
+#[cfg(feature = "std")]
+impl Backoff for SpinThenYield {
+    fn backoff(&mut self) {
+        if self.step < SPIN_LIMIT {
+            spin(self.step);
+            self.step += 1;
+        } else {
+            std::thread::yield_now();
+        }
+    }
+}
+
+/// Spins exponentially for a while, then sleeps for exponentially increasing durations (up to a limit) in between
+/// attempts.
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub struct SpinThenSleep {
+    step: u32,
+}
+
+#[cfg(feature = "std")]
+impl Backoff for SpinThenSleep {
+    fn backoff(&mut self) {
+        if self.step < SPIN_LIMIT {
+            spin(self.step);
+        } else {
+            std::thread::sleep(Duration::from_micros(1 << (self.step - SPIN_LIMIT)));
+        }
+
+        if self.step < SPIN_LIMIT + SLEEP_LIMIT {
+            self.step += 1;
+        }
+    }
+}
+
+#[test]
+fn test_backoff() {
+    fn count<B: Backoff>() {
+        let l = SpinLock::<_, B>::with_backoff(0);
+
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..100 {
+                        *l.lock() += 1;
+                    }
+                });
+            }
+        });
+
+        assert_eq!(*l.lock(), 400);
+    }
+
+    count::<NoBackoff>();
+    count::<ExponentialSpin>();
+    #[cfg(feature = "std")]
+    count::<SpinThenYield>();
+    #[cfg(feature = "std")]
+    count::<SpinThenSleep>();
+}
@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use super::backoff::Backoff;
+use super::spin_lock_guard::{Guard, SpinLock};
+
+///
+/// Pros:
+///   - Waiting threads are parked, instead of spinning on the lock until some condition holds.
+///   - Notifying without any waiting threads is a single atomic increment (and a load).
+///
+/// Cons:
+///   - Waiting and notifying take a (spin) lock on the waiter queue.
+///   - Spurious wakeups: a waiting thread may also return after a notification that was meant for another thread.
+///
+/// Notes:
+///   - A waiting thread reads the notification counter and registers itself while still holding the lock, and then
+///     parks until the counter changes. A notification that comes in between unlocking and parking is not lost: it
+///     either changed the counter before we check it, or it unparks us (which makes the next park return immediately).
+///   - The number of waiters is only changed while holding the lock, and notifications are expected to follow a change
+///     that was made while holding the lock. So, just like for the condition itself, the lock makes sure a notifying
+///     thread sees the waiters that registered before (and a waiter that registered after sees the changed condition).
+///
+pub struct Condvar {
+    counter: AtomicU32,
+    num_waiters: AtomicUsize,
+    waiters: SpinLock<VecDeque<Thread>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitTimeoutResult(bool);
+
+impl WaitTimeoutResult {
+    pub fn timed_out(&self) -> bool {
+        self.0
+    }
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self {
+            counter: AtomicU32::new(0),
+            num_waiters: AtomicUsize::new(0),
+            waiters: SpinLock::new(VecDeque::new()),
+        }
+    }
+
+    pub fn notify_one(&self) {
+        if self.num_waiters.load(Ordering::Relaxed) > 0 {
+            self.counter.fetch_add(1, Ordering::Release);
+
+            if let Some(thread) = self.waiters.lock().pop_front() {
+                thread.unpark();
+            }
+        }
+    }
+
+    pub fn notify_all(&self) {
+        if self.num_waiters.load(Ordering::Relaxed) > 0 {
+            self.counter.fetch_add(1, Ordering::Release);
+
+            for thread in self.waiters.lock().drain(..) {
+                thread.unpark();
+            }
+        }
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn wait<'a, T, B: Backoff>(&self, guard: Guard<'a, T, B>) -> Guard<'a, T, B> {
+        self.wait_until(guard, None).0
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn wait_while<'a, T, B, F>(
+        &self,
+        mut guard: Guard<'a, T, B>,
+        mut condition: F,
+    ) -> Guard<'a, T, B>
+    where
+        B: Backoff,
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            guard = self.wait(guard);
+        }
+
+        guard
+    }
+
+    // A timeout that does not fit in an 'Instant' waits forever.
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn wait_timeout<'a, T, B: Backoff>(
+        &self,
+        guard: Guard<'a, T, B>,
+        timeout: Duration,
+    ) -> (Guard<'a, T, B>, WaitTimeoutResult) {
+        self.wait_until(guard, Instant::now().checked_add(timeout))
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    fn wait_until<'a, T, B: Backoff>(
+        &self,
+        guard: Guard<'a, T, B>,
+        deadline: Option<Instant>,
+    ) -> (Guard<'a, T, B>, WaitTimeoutResult) {
+        let counter = self.counter.load(Ordering::Relaxed);
+        let current = thread::current();
+
+        self.num_waiters.fetch_add(1, Ordering::Relaxed);
+        self.waiters.lock().push_back(current.clone());
+
+        let lock = guard.lock;
+        drop(guard);
+
+        while self.counter.load(Ordering::Acquire) == counter {
+            match deadline {
+                None => thread::park(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break;
+                    }
+
+                    thread::park_timeout(deadline - now);
+                }
+            }
+        }
+
+        //
+        // A notifying thread takes us out of the queue. If we are still in there, we either timed out, or woke up
+        //  because of a notification for another thread. Either way we have to remove ourselves, so a later notification
+        //  does not go to waste on us. If we are not in there, we were notified (possibly right as we timed out).
+        //
+        let timed_out = {
+            let mut waiters = self.waiters.lock();
+            match waiters.iter().position(|t| t.id() == current.id()) {
+                Some(i) => {
+                    waiters.remove(i);
+                    self.counter.load(Ordering::Relaxed) == counter
+                }
+                None => false,
+            }
+        };
+
+        let guard = lock.lock();
+        self.num_waiters.fetch_sub(1, Ordering::Relaxed);
+
+        (guard, WaitTimeoutResult(timed_out))
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_condvar() {
+    let queue = SpinLock::new(VecDeque::new());
+    let condvar = Condvar::new();
+
+    thread::scope(|s| {
+        for _ in 0..2 {
+            s.spawn(|| {
+                let mut received = 0;
+                while received < 50 {
+                    let mut q = condvar.wait_while(queue.lock(), |q| q.is_empty());
+                    q.pop_front().unwrap();
+                    received += 1;
+                }
+            });
+        }
+
+        for i in 0..100 {
+            queue.lock().push_back(i);
+            condvar.notify_one();
+
+            if i % 10 == 0 {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+    });
+
+    let (g, result) = condvar.wait_timeout(queue.lock(), Duration::from_millis(10));
+    assert!(result.timed_out());
+    assert!(g.is_empty());
+    drop(g);
+
+    assert_eq!(condvar.num_waiters.load(Ordering::Relaxed), 0);
+    assert!(condvar.waiters.lock().is_empty());
+}
@@ -0,0 +1,347 @@
+#[cfg(feature = "deadlock-detection")]
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    panic::Location,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+    thread::{self, ThreadId},
+};
+
+#[cfg(all(test, feature = "deadlock-detection"))]
+use super::spin_lock_guard::{ArcLock, SpinLock};
+#[cfg(all(test, feature = "deadlock-detection"))]
+use std::sync::Arc;
+
+//
+// Lock order deadlock detection, enabled by the 'deadlock-detection' feature. Without the feature, 'LockId' is
+//  zero-sized and all of its functions are empty.
+//
+// Every thread keeps track of the locks it holds. Whenever a thread is about to block on a lock, an edge from every
+//  held lock to the new one is added to a global lock order graph. If the graph already has a path from the new lock
+//  back to one of the held locks, two threads can end up waiting for each other: we panic with the lock sites of both
+//  orders instead of (maybe, some day) hanging silently.
+//
+// Locks are identified by a unique number that is assigned when the lock is first used, not by their address: a new
+//  lock at the address of a dropped one must not inherit its edges.
+//
+// The held locks are kept in a global list per acquiring thread, rather than in a thread local: a guard may be
+//  dropped on another thread (e.g. an owned guard moved into a spawned thread), and the lock must then be removed from
+//  the set of the thread that took it.
+//
+
+pub(crate) struct LockId {
+    #[cfg(feature = "deadlock-detection")]
+    id: AtomicUsize,
+}
+
+#[cfg(feature = "deadlock-detection")]
+type Site = &'static Location<'static>;
+
+// The locks held by a thread, in the order in which they were taken.
+#[cfg(feature = "deadlock-detection")]
+type Held = Vec<(usize, Site)>;
+
+// The sites at which the locks of an edge were taken, in order.
+#[cfg(feature = "deadlock-detection")]
+#[derive(Clone, Copy)]
+struct Edge {
+    from: Site,
+    to: Site,
+}
+
+#[cfg(feature = "deadlock-detection")]
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+#[cfg(feature = "deadlock-detection")]
+static GRAPH: Mutex<BTreeMap<usize, BTreeMap<usize, Edge>>> = Mutex::new(BTreeMap::new());
+
+#[cfg(feature = "deadlock-detection")]
+static HELD: Mutex<Vec<(ThreadId, Held)>> = Mutex::new(Vec::new());
+
+//
+// Depth-first search for a path between two locks. Returns the first edge of the path, which is enough to point the
+//  user at the conflicting lock order.
+//
+#[cfg(feature = "deadlock-detection")]
+fn find_path(
+    graph: &BTreeMap<usize, BTreeMap<usize, Edge>>,
+    from: usize,
+    to: usize,
+) -> Option<Edge> {
+    let mut visited = BTreeSet::new();
+    let mut stack: Vec<(usize, Edge)> = graph.get(&from)?.iter().map(|(&n, &e)| (n, e)).collect();
+
+    while let Some((node, first)) = stack.pop() {
+        if node == to {
+            return Some(first);
+        }
+
+        if visited.insert(node) {
+            if let Some(next) = graph.get(&node) {
+                stack.extend(next.keys().map(|&n| (n, first)));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(feature = "deadlock-detection")]
+fn held_by_current_thread(held: &mut Vec<(ThreadId, Held)>) -> &mut Held {
+    let thread = thread::current().id();
+    let i = match held.iter().position(|&(t, _)| t == thread) {
+        Some(i) => i,
+        None => {
+            held.push((thread, Vec::new()));
+            held.len() - 1
+        }
+    };
+
+    &mut held[i].1
+}
+
+//
+// Checks taking lock 'id' at 'site' against the locks held by the current thread, adding the new lock order edges to
+//  the graph if there is no conflict. A shared (read) acquisition of a lock that the thread already holds is not
+//  reported, as readers do not exclude each other.
+//
+#[cfg(feature = "deadlock-detection")]
+fn find_conflict(held: &[(usize, Site)], id: usize, site: Site, shared: bool) -> Option<String> {
+    let mut graph = GRAPH.lock().unwrap_or_else(PoisonError::into_inner);
+
+    for &(held_id, held_site) in held {
+        if held_id == id {
+            if shared {
+                continue;
+            }
+
+            return Some(format!(
+                "lock taken at {site} is already held by this thread, taken at {held_site}"
+            ));
+        }
+
+        if let Some(edge) = find_path(&graph, id, held_id) {
+            return Some(format!(
+                "lock taken at {site} while holding the lock taken at {held_site}, but elsewhere a lock \
+                 taken at {} was held while taking the lock at {}",
+                edge.from, edge.to
+            ));
+        }
+
+        graph.entry(held_id).or_default().entry(id).or_insert(Edge {
+            from: held_site,
+            to: site,
+        });
+    }
+
+    None
+}
+
+#[cfg(feature = "deadlock-detection")]
+impl LockId {
+    pub(crate) const fn new() -> Self {
+        Self {
+            id: AtomicUsize::new(0),
+        }
+    }
+
+    fn get(&self) -> usize {
+        let id = self.id.load(Ordering::Relaxed);
+        if id != 0 {
+            return id;
+        }
+
+        let new = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        match self
+            .id
+            .compare_exchange(0, new, Ordering::Relaxed, Ordering::Relaxed)
+        {
+            Ok(_) => new,
+            Err(id) => id,
+        }
+    }
+
+    //
+    // To be called before blocking on the lock, followed by 'acquired' once it is taken. A thread that is still waiting
+    //  for the lock does not hold it yet: while it waits, the lock can still be released by its current holder.
+    //
+    #[track_caller]
+    pub(crate) fn acquiring(&self) {
+        self.check(false);
+    }
+
+    // Like 'acquiring', for a lock that is shared with other threads (i.e. a read lock).
+    #[track_caller]
+    pub(crate) fn acquiring_shared(&self) {
+        self.check(true);
+    }
+
+    #[track_caller]
+    fn check(&self, shared: bool) {
+        let id = self.get();
+
+        let mut held = HELD.lock().unwrap_or_else(PoisonError::into_inner);
+        let conflict = find_conflict(
+            held_by_current_thread(&mut held),
+            id,
+            Location::caller(),
+            shared,
+        );
+        drop(held);
+
+        if let Some(conflict) = conflict {
+            panic!("potential deadlock: {conflict}");
+        }
+    }
+
+    // To be called after taking the lock, after 'acquiring' or without blocking (e.g. by a successful 'try_lock').
+    #[track_caller]
+    pub(crate) fn acquired(&self) {
+        let id = self.get();
+        let mut held = HELD.lock().unwrap_or_else(PoisonError::into_inner);
+        held_by_current_thread(&mut held).push((id, Location::caller()));
+    }
+
+    //
+    // Guards may be dropped in any order, or even on another thread. An exclusive lock is only ever held by one thread
+    //  at a time (a reentrant lock by its owner several times), so the thread that took it is the one whose set
+    //  contains it. A read lock can be held by several threads: a read guard is normally dropped on the thread that
+    //  took it, so that thread's own set is checked first.
+    //
+    pub(crate) fn released(&self) {
+        let id = self.get();
+        let thread = thread::current().id();
+        let mut held = HELD.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let holds = |locks: &Held| locks.iter().any(|&(held_id, _)| held_id == id);
+        let i = held
+            .iter()
+            .position(|(t, locks)| *t == thread && holds(locks))
+            .or_else(|| held.iter().position(|(_, locks)| holds(locks)));
+
+        if let Some(i) = i {
+            let locks = &mut held[i].1;
+            if let Some(j) = locks.iter().rposition(|&(held_id, _)| held_id == id) {
+                locks.remove(j);
+            }
+
+            // Don't keep an empty set around for every thread that ever took a lock.
+            if locks.is_empty() {
+                held.swap_remove(i);
+            }
+        }
+    }
+}
+
+//
+// A dropped lock can no longer take part in a deadlock, and its id is never used again. Removing its node (and every
+//  edge to it) keeps the graph from growing with every lock ever created, and from reporting a lock order through a
+//  lock that is gone.
+//
+#[cfg(feature = "deadlock-detection")]
+impl Drop for LockId {
+    fn drop(&mut self) {
+        let id = *self.id.get_mut();
+        if id == 0 {
+            return;
+        }
+
+        let mut graph = GRAPH.lock().unwrap_or_else(PoisonError::into_inner);
+        graph.remove(&id);
+        graph.retain(|_, edges| {
+            edges.remove(&id);
+            !edges.is_empty()
+        });
+    }
+}
+
+#[cfg(not(feature = "deadlock-detection"))]
+impl LockId {
+    pub(crate) const fn new() -> Self {
+        Self {}
+    }
+
+    #[inline(always)]
+    pub(crate) fn acquiring(&self) {}
+
+    #[inline(always)]
+    pub(crate) fn acquiring_shared(&self) {}
+
+    #[inline(always)]
+    pub(crate) fn acquired(&self) {}
+
+    #[inline(always)]
+    pub(crate) fn released(&self) {}
+}
+
+#[cfg(feature = "deadlock-detection")]
+#[test]
+fn test_deadlock_detection() {
+    let a = SpinLock::new(0);
+    let b = SpinLock::new(0);
+
+    {
+        let _ga = a.lock();
+        let _gb = b.lock();
+    }
+
+    thread::scope(|s| {
+        let t = s.spawn(|| {
+            let _gb = b.lock();
+            let _ga = a.lock();
+        });
+
+        let e = t.join().unwrap_err();
+        let message = e.downcast_ref::<String>().unwrap();
+        assert!(message.starts_with("potential deadlock"));
+        assert!(message.contains(file!()));
+
+        let t = s.spawn(|| {
+            let _ga = a.lock();
+            let _ga = a.lock();
+        });
+
+        assert!(t.join().is_err());
+    });
+
+    // Both locks were released while unwinding, and taking them in the original order is still fine.
+    let _ga = a.lock();
+    let _gb = b.try_lock().unwrap();
+}
+
+#[cfg(feature = "deadlock-detection")]
+#[test]
+fn test_deadlock_detection_released_elsewhere() {
+    let l = Arc::new(SpinLock::new(0));
+
+    // The owned guard is dropped on another thread, which must release the lock for this thread.
+    let g = l.lock_owned();
+    thread::spawn(move || drop(g)).join().unwrap();
+
+    *l.lock() += 1;
+}
+
+#[cfg(feature = "deadlock-detection")]
+#[test]
+fn test_deadlock_detection_dropped_lock() {
+    let a = SpinLock::new(0);
+    let b = SpinLock::new(0);
+
+    {
+        let d = SpinLock::new(0);
+
+        {
+            let _ga = a.lock();
+            let _gd = d.lock();
+        }
+
+        let _gd = d.lock();
+        let _gb = b.lock();
+    }
+
+    // The only path from 'a' to 'b' went through 'd', which is gone.
+    let _gb = b.lock();
+    let _ga = a.lock();
+}
@@ -1,3 +1,19 @@
+pub mod backoff;
+#[cfg(feature = "std")]
+pub mod condvar;
+mod deadlock;
+pub mod raw_lock;
+#[cfg(feature = "std")]
+pub mod spin_lock_adaptive;
+pub mod spin_lock_clh;
 pub mod spin_lock_guard;
+pub mod spin_lock_mcs;
+#[cfg(feature = "std")]
+pub mod spin_lock_poison;
+#[cfg(feature = "std")]
+pub mod spin_lock_reentrant;
 pub mod spin_lock_simple;
+pub mod spin_lock_ticket;
 pub mod spin_lock_unsafe;
+pub mod spin_rwlock;
+pub mod stats;
@@ -0,0 +1,139 @@
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+
+use super::deadlock::LockId;
+#[cfg(feature = "stats")]
+use super::stats::LockStats;
+
+#[cfg(test)]
+use super::spin_lock_simple;
+#[cfg(test)]
+use std::thread;
+
+/// A raw lock only implements the locking algorithm, without any data to protect. 'Lock' wraps a value with any raw
+/// lock to get the usual safe, guarded interface, so the wrapper (and its guard) only has to be written once.
+///
+/// # Safety
+/// Implementors must guarantee mutual exclusion: after 'lock' returned (or 'try_lock' returned true), no other call to
+/// 'lock' may return (and 'try_lock' must return false) until 'unlock' is called. Unlocking must synchronize with the
+/// next acquisition, i.e. release/acquire semantics.
+pub unsafe trait RawLock {
+    /// An unlocked instance, so that 'Lock::new' can be a const fn.
+    const INIT: Self;
+
+    fn lock(&self);
+
+    fn try_lock(&self) -> bool;
+
+    /// # Safety
+    /// The lock must be held by the current context.
+    unsafe fn unlock(&self);
+
+    /// Forwarded by 'Lock::stats', as the raw lock itself is not accessible through 'Lock'.
+    #[cfg(feature = "stats")]
+    fn stats(&self) -> LockStats;
+}
+
+///
+/// Pros:
+///   - Same safe, guarded interface as the guard-based spin lock, for any locking algorithm.
+///
+/// Cons:
+///   - Raw locks without a guard have to keep any per-acquisition state (e.g. a queue node) in the lock itself.
+///
+pub struct Lock<R, T> {
+    // Not public: a raw lock that can be unlocked safely would allow taking a second guard while the first is alive.
+    pub(super) raw: R,
+    id: LockId,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<R, T> Sync for Lock<R, T>
+where
+    R: Sync,
+    T: Send,
+{
+}
+
+pub struct Guard<'a, R: RawLock, T> {
+    lock: &'a Lock<R, T>,
+}
+
+unsafe impl<R: RawLock + Sync, T> Sync for Guard<'_, R, T> where T: Sync {}
+
+impl<R: RawLock, T> Deref for Guard<'_, R, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<R: RawLock, T> DerefMut for Guard<'_, R, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<R: RawLock, T> Drop for Guard<'_, R, T> {
+    fn drop(&mut self) {
+        self.lock.id.released();
+        unsafe { self.lock.raw.unlock() };
+    }
+}
+
+impl<R: RawLock, T> Lock<R, T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            raw: R::INIT,
+            id: LockId::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn lock(&self) -> Guard<'_, R, T> {
+        self.id.acquiring();
+        self.raw.lock();
+        self.id.acquired();
+
+        Guard { lock: self }
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn try_lock(&self) -> Option<Guard<'_, R, T>> {
+        if !self.raw.try_lock() {
+            return None;
+        }
+
+        self.id.acquired();
+        Some(Guard { lock: self })
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.raw.stats()
+    }
+}
+
+#[test]
+fn test_lock() {
+    let l = Lock::<spin_lock_simple::SpinLock, _>::new(0);
+
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..100 {
+                    *l.lock() += 1;
+                }
+            });
+        }
+    });
+
+    let g = l.try_lock().unwrap();
+    assert_eq!(*g, 400);
+    assert!(l.try_lock().is_none());
+
+    #[cfg(feature = "stats")]
+    assert_eq!(l.stats().acquisitions, 401);
+}
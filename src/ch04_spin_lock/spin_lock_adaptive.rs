@@ -0,0 +1,190 @@
+use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+use std::thread::{self, Thread};
+
+use super::deadlock::LockId;
+use super::spin_lock_guard::SpinLock;
+
+#[cfg(feature = "stats")]
+use super::stats::LockStats;
+use super::stats::{HoldTimer, Stats};
+
+#[cfg(test)]
+use std::time::Duration;
+
+// The number of attempts to take the lock by spinning, before parking the thread.
+const SPIN_LIMIT: u32 = 100;
+
+///
+/// Pros:
+///   - Same safe, guarded interface as the guard-based spin lock.
+///   - Short critical sections get the fast path of a spin lock, long ones do not keep the waiting threads spinning.
+///
+/// Cons:
+///   - Unfair: a thread that was just unparked competes with threads that are still in their spin phase.
+///   - Unlocking takes the (spin) lock on the waiter queue if there are parked threads.
+///
+/// Notes:
+///   - A waiter enqueues itself (under the waiter queue lock) and then makes a last attempt to take the lock, before it
+///     parks. The unlocking thread releases the lock and then checks for parked threads. With a SeqCst fence in between
+///     on both sides, at least one of them sees the store of the other: either the waiter gets the lock, or the
+///     unlocking thread finds the waiter in the queue to unpark it.
+///   - Thread parking may wake up spuriously. A thread that is still in the queue upon waking up is not enqueued twice.
+///
+pub struct AdaptiveLock<T> {
+    locked: AtomicBool,
+    parked: AtomicUsize,
+    waiters: SpinLock<VecDeque<Thread>>,
+    stats: Stats,
+    id: LockId,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for AdaptiveLock<T> where T: Send {}
+
+pub struct Guard<'a, T> {
+    lock: &'a AdaptiveLock<T>,
+    timer: HoldTimer,
+}
+
+unsafe impl<T> Sync for Guard<'_, T> where T: Sync {}
+
+impl<T> Deref for Guard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for Guard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for Guard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.id.released();
+        self.lock.stats.released(self.timer);
+        self.lock.locked.store(false, Ordering::Release);
+
+        fence(Ordering::SeqCst);
+
+        if self.lock.parked.load(Ordering::Relaxed) > 0 {
+            let mut waiters = self.lock.waiters.lock();
+            if let Some(thread) = waiters.pop_front() {
+                self.lock.parked.store(waiters.len(), Ordering::Relaxed);
+                thread.unpark();
+            }
+        }
+    }
+}
+
+impl<T> AdaptiveLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            parked: AtomicUsize::new(0),
+            waiters: SpinLock::new(VecDeque::new()),
+            stats: Stats::new(),
+            id: LockId::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn lock(&self) -> Guard<'_, T> {
+        self.id.acquiring();
+
+        for spins in 0..SPIN_LIMIT {
+            if self.try_acquire() {
+                self.id.acquired();
+                return self.guard(spins.into());
+            }
+
+            std::hint::spin_loop();
+        }
+
+        let current = thread::current();
+
+        loop {
+            {
+                let mut waiters = self.waiters.lock();
+                if !waiters.iter().any(|t| t.id() == current.id()) {
+                    waiters.push_back(current.clone());
+                    self.parked.store(waiters.len(), Ordering::Relaxed);
+                }
+
+                fence(Ordering::SeqCst);
+
+                if !self.locked.swap(true, Ordering::Acquire) {
+                    waiters.retain(|t| t.id() != current.id());
+                    self.parked.store(waiters.len(), Ordering::Relaxed);
+
+                    self.id.acquired();
+                    return self.guard(SPIN_LIMIT.into());
+                }
+            }
+
+            thread::park();
+        }
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn try_lock(&self) -> Option<Guard<'_, T>> {
+        if !self.try_acquire() {
+            return None;
+        }
+
+        self.id.acquired();
+        Some(self.guard(0))
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.stats.snapshot()
+    }
+
+    fn try_acquire(&self) -> bool {
+        !self.locked.load(Ordering::Relaxed) && !self.locked.swap(true, Ordering::Acquire)
+    }
+
+    // Must only be called with the lock held.
+    fn guard(&self, spins: u64) -> Guard<'_, T> {
+        Guard {
+            lock: self,
+            timer: self.stats.acquired(spins),
+        }
+    }
+}
+
+#[test]
+fn test_adaptive_lock() {
+    let l = AdaptiveLock::new(0);
+
+    thread::scope(|s| {
+        let g = l.lock();
+
+        for _ in 0..3 {
+            s.spawn(|| {
+                *l.lock() += 1;
+            });
+        }
+
+        //
+        // Holding on to the lock (for way longer than the spin phase) makes all other threads park.
+        //
+        while l.parked.load(Ordering::Relaxed) != 3 {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        drop(g);
+    });
+
+    assert_eq!(*l.lock(), 3);
+    assert_eq!(l.parked.load(Ordering::Relaxed), 0);
+    assert!(l.waiters.lock().is_empty());
+}
@@ -0,0 +1,223 @@
+#[cfg(feature = "std")]
+use core::cell::Cell;
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+use alloc::boxed::Box;
+
+use super::deadlock::LockId;
+#[cfg(feature = "stats")]
+use super::stats::LockStats;
+use super::stats::{HoldTimer, Stats};
+
+#[cfg(test)]
+use std::thread;
+
+///
+/// Pros:
+///   - Same safe, guarded interface as the guard-based spin lock.
+///   - Fair: threads acquire the lock in the order in which they started waiting for it (FIFO).
+///   - Every waiter spins on the node of its predecessor, which no other thread spins on.
+///   - Unlocking never waits, it is a single store.
+///
+/// Cons:
+///   - Creating a lock allocates a node, and so does the first acquisition on every thread (or every acquisition,
+///     without the 'std' feature).
+///   - No 'try_lock': once a thread has enqueued itself, it cannot leave the queue before it is its turn.
+///
+/// Notes:
+///   - The queue is implicit: a thread swaps its (locked) node into the tail, and spins on the node it got back from
+///     the swap, i.e. the node of its predecessor. Unlocking is done by clearing the flag of the own node.
+///   - When a thread has acquired the lock, nobody refers to the node of its predecessor anymore. Instead of freeing it,
+///     the guard recycles it as the node for the next acquisition on the same thread. Without the 'std' feature there
+///     are no thread locals to keep it in, so the guard frees it after all.
+///
+pub struct SpinLock<T> {
+    tail: AtomicPtr<Node>,
+    stats: Stats,
+    id: LockId,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for SpinLock<T> where T: Send {}
+
+// Aligned to a (typical) cache line, so that waiters spinning on neighbouring nodes do not interfere.
+#[repr(align(64))]
+struct Node {
+    locked: AtomicBool,
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static SPARE_NODE: Cell<Option<Box<Node>>> = const { Cell::new(None) };
+}
+
+impl Node {
+    fn new() -> Box<Node> {
+        Box::new(Node {
+            locked: AtomicBool::new(true),
+        })
+    }
+
+    //
+    // The thread local may already be destroyed when a lock is used from the destructor of another thread local, in
+    //  which case we just allocate a new node (or free the recycled one).
+    //
+    #[cfg(feature = "std")]
+    fn acquire() -> NonNull<Node> {
+        let node = SPARE_NODE
+            .try_with(|spare| spare.take())
+            .ok()
+            .flatten()
+            .unwrap_or_else(Node::new);
+
+        node.locked.store(true, Ordering::Relaxed);
+        NonNull::from(Box::leak(node))
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn acquire() -> NonNull<Node> {
+        NonNull::from(Box::leak(Node::new()))
+    }
+
+    #[cfg(feature = "std")]
+    unsafe fn recycle(node: NonNull<Node>) {
+        let node = Box::from_raw(node.as_ptr());
+        let _ = SPARE_NODE.try_with(|spare| spare.set(Some(node)));
+    }
+
+    #[cfg(not(feature = "std"))]
+    unsafe fn recycle(node: NonNull<Node>) {
+        drop(Box::from_raw(node.as_ptr()));
+    }
+}
+
+pub struct Guard<'a, T> {
+    lock: &'a SpinLock<T>,
+    node: NonNull<Node>,
+    pred: NonNull<Node>,
+    timer: HoldTimer,
+}
+
+// The node pointers make the guard neither Send nor Sync, but both nodes are exclusively ours until the guard is
+//  dropped. Dropping the guard on another thread just recycles the predecessor node on that thread.
+unsafe impl<T> Send for Guard<'_, T> where T: Send {}
+unsafe impl<T> Sync for Guard<'_, T> where T: Sync {}
+
+impl<T> Deref for Guard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for Guard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for Guard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.id.released();
+        self.lock.stats.released(self.timer);
+
+        //
+        // After this store our node belongs to our successor (or it stays the tail of the queue).
+        //
+        unsafe { self.node.as_ref() }
+            .locked
+            .store(false, Ordering::Release);
+
+        unsafe { Node::recycle(self.pred) };
+    }
+}
+
+impl<T> SpinLock<T> {
+    pub fn new(value: T) -> Self {
+        let node = Node::acquire();
+        unsafe { node.as_ref() }
+            .locked
+            .store(false, Ordering::Relaxed);
+
+        Self {
+            tail: AtomicPtr::new(node.as_ptr()),
+            stats: Stats::new(),
+            id: LockId::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    //
+    // The swap on the tail is acquire/release: acquire to see the initialized node of our predecessor, release to
+    //  publish our own (locked) node to our successor. The acquire load on the predecessor node pairs with the release
+    //  store in the guard of our predecessor.
+    //
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn lock(&self) -> Guard<'_, T> {
+        self.id.acquiring();
+
+        let node = Node::acquire();
+
+        let pred = self.tail.swap(node.as_ptr(), Ordering::AcqRel);
+        let pred = unsafe { NonNull::new_unchecked(pred) };
+
+        let mut spins = 0;
+        while unsafe { pred.as_ref() }.locked.load(Ordering::Acquire) {
+            core::hint::spin_loop();
+            spins += 1;
+        }
+
+        self.id.acquired();
+
+        Guard {
+            lock: self,
+            node,
+            pred,
+            timer: self.stats.acquired(spins),
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.stats.snapshot()
+    }
+}
+
+impl<T> Drop for SpinLock<T> {
+    fn drop(&mut self) {
+        unsafe { drop(Box::from_raw(*self.tail.get_mut())) };
+    }
+}
+
+#[test]
+fn test_spin_lock() {
+    let l = SpinLock::new(0);
+
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..100 {
+                    *l.lock() += 1;
+                }
+            });
+        }
+    });
+
+    assert_eq!(*l.lock(), 400);
+
+    //
+    // The node of the predecessor is recycled for the next acquisition on this thread.
+    //
+    #[cfg(feature = "std")]
+    {
+        let g = l.lock();
+        let pred = g.pred;
+        drop(g);
+
+        assert_eq!(l.lock().node, pred);
+    }
+}
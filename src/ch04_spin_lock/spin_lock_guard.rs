@@ -1,9 +1,25 @@
-use std::cell::UnsafeCell;
-use std::ops::{Deref, DerefMut};
-use std::sync::atomic::{AtomicBool, Ordering};
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, Ordering};
+#[cfg(any(feature = "std", test))]
+use core::time::Duration;
+
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use super::backoff::{Backoff, NoBackoff};
+use super::deadlock::LockId;
+#[cfg(feature = "stats")]
+use super::stats::LockStats;
+use super::stats::{HoldTimer, Stats};
+use crate::ch06_arc::{arc1_basic, arc2_weak, arc3_optimized};
 
 #[cfg(test)]
-use std::{thread, time::Duration};
+use std::thread;
 
 ///
 /// Pros:
@@ -14,23 +30,42 @@ use std::{thread, time::Duration};
 /// Cons:
 ///   - ...
 ///
+pub struct SpinLock<T, B = NoBackoff> {
+    state: State,
+    value: UnsafeCell<T>,
+    _backoff: PhantomData<fn() -> B>,
+}
 
-pub struct SpinLock<T> {
+//
+// Everything needed to unlock, independent of the value type. This way, a mapped guard can unlock without knowing the
+//  type of the original value.
+//
+struct State {
     locked: AtomicBool,
-    value: UnsafeCell<T>,
+    stats: Stats,
+    id: LockId,
+}
+
+impl State {
+    fn unlock(&self, timer: HoldTimer) {
+        self.id.released();
+        self.stats.released(timer);
+        self.locked.store(false, Ordering::Release);
+    }
 }
 
 // UnsafeCell is not Sync (it is Send only). However, if 'T' is Send, we can treat the spin lock as Sync.
-unsafe impl<T> Sync for SpinLock<T> where T: Send {}
+unsafe impl<T, B> Sync for SpinLock<T, B> where T: Send {}
 
-pub struct Guard<'a, T> {
-    lock: &'a SpinLock<T>,
+pub struct Guard<'a, T, B = NoBackoff> {
+    pub(super) lock: &'a SpinLock<T, B>,
+    timer: HoldTimer,
 }
 
 // The Guard, essentially a reference to a spin lock for value type 'T' access, can only be Sync if 'T' is as well.
-unsafe impl<T> Sync for Guard<'_, T> where T: Sync {}
+unsafe impl<T, B> Sync for Guard<'_, T, B> where T: Sync {}
 
-impl<T> Deref for Guard<'_, T> {
+impl<T, B> Deref for Guard<'_, T, B> {
     type Target = T;
 
     fn deref(&self) -> &T {
@@ -38,32 +73,316 @@ impl<T> Deref for Guard<'_, T> {
     }
 }
 
-impl<T> DerefMut for Guard<'_, T> {
+impl<T, B> DerefMut for Guard<'_, T, B> {
     fn deref_mut(&mut self) -> &mut T {
         unsafe { &mut *self.lock.value.get() }
     }
 }
 
-impl<T> Drop for Guard<'_, T> {
+impl<T, B> Drop for Guard<'_, T, B> {
     fn drop(&mut self) {
-        self.lock.locked.store(false, Ordering::Release);
+        self.lock.state.unlock(self.timer);
+    }
+}
+
+//
+// Mapping is done through associated functions (i.e. 'Guard::map(g, ...)') instead of methods, so that they do not
+//  shadow methods of 'T' with the same name.
+//
+// The closure runs while the original guard is still alive, so a panic inside of it releases the lock as usual. Only
+//  after it returned, the original guard is forgotten: from then on, the mapped guard is responsible for unlocking.
+//
+impl<'a, T, B> Guard<'a, T, B> {
+    pub fn map<U, F>(guard: Self, f: F) -> MappedGuard<'a, U>
+    where
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        let lock = guard.lock;
+        let value = NonNull::from(f(unsafe { &mut *lock.value.get() }));
+        let timer = guard.timer;
+        mem::forget(guard);
+
+        MappedGuard {
+            state: &lock.state,
+            timer,
+            value,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn try_map<U, F>(guard: Self, f: F) -> Result<MappedGuard<'a, U>, Self>
+    where
+        F: FnOnce(&mut T) -> Option<&mut U>,
+    {
+        let lock = guard.lock;
+        match f(unsafe { &mut *lock.value.get() }) {
+            Some(value) => {
+                let timer = guard.timer;
+                mem::forget(guard);
+
+                Ok(MappedGuard {
+                    state: &lock.state,
+                    timer,
+                    value: NonNull::from(value),
+                    _marker: PhantomData,
+                })
+            }
+            None => Err(guard),
+        }
+    }
+}
+
+//
+// A guard that still holds the lock, but only gives access to a part of the locked value. The original value type is
+//  erased, all that is needed to unlock is the lock state itself.
+//
+pub struct MappedGuard<'a, U> {
+    state: &'a State,
+    timer: HoldTimer,
+    value: NonNull<U>,
+    _marker: PhantomData<&'a mut U>,
+}
+
+// The guard behaves like a '&mut U', which is Send/Sync if 'U' is.
+unsafe impl<U> Send for MappedGuard<'_, U> where U: Send {}
+unsafe impl<U> Sync for MappedGuard<'_, U> where U: Sync {}
+
+impl<U> Deref for MappedGuard<'_, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        unsafe { self.value.as_ref() }
+    }
+}
+
+impl<U> DerefMut for MappedGuard<'_, U> {
+    fn deref_mut(&mut self) -> &mut U {
+        unsafe { self.value.as_mut() }
+    }
+}
+
+impl<U> Drop for MappedGuard<'_, U> {
+    fn drop(&mut self) {
+        self.state.unlock(self.timer);
+    }
+}
+
+impl<'a, U> MappedGuard<'a, U> {
+    pub fn map<V, F>(mut guard: Self, f: F) -> MappedGuard<'a, V>
+    where
+        F: FnOnce(&mut U) -> &mut V,
+    {
+        let value = NonNull::from(f(unsafe { guard.value.as_mut() }));
+        let (state, timer) = (guard.state, guard.timer);
+        mem::forget(guard);
+
+        MappedGuard {
+            state,
+            timer,
+            value,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn try_map<V, F>(mut guard: Self, f: F) -> Result<MappedGuard<'a, V>, Self>
+    where
+        F: FnOnce(&mut U) -> Option<&mut V>,
+    {
+        match f(unsafe { guard.value.as_mut() }) {
+            Some(value) => {
+                let value = NonNull::from(value);
+                let (state, timer) = (guard.state, guard.timer);
+                mem::forget(guard);
+
+                Ok(MappedGuard {
+                    state,
+                    timer,
+                    value,
+                    _marker: PhantomData,
+                })
+            }
+            None => Err(guard),
+        }
+    }
+}
+
+/// A shared pointer to a spin lock, that keeps the lock alive. An owned guard holds a clone of the pointer instead of a
+/// reference, so it has no lifetime: it can be stored in a struct or moved into a (non-scoped) thread.
+///
+/// # Safety
+/// Dereferencing the pointer, or any of its clones, must always give the same lock, for as long as any of them is alive.
+pub unsafe trait ArcLock<T, B = NoBackoff>: Deref<Target = SpinLock<T, B>> + Clone {
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    fn lock_owned(&self) -> OwnedGuard<T, B, Self>
+    where
+        B: Backoff,
+    {
+        OwnedGuard::new(self, self.lock())
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    fn try_lock_owned(&self) -> Option<OwnedGuard<T, B, Self>>
+    where
+        B: Backoff,
+    {
+        self.try_lock().map(|guard| OwnedGuard::new(self, guard))
+    }
+}
+
+unsafe impl<T, B> ArcLock<T, B> for Arc<SpinLock<T, B>> {}
+unsafe impl<T, B> ArcLock<T, B> for arc1_basic::Arc<SpinLock<T, B>> {}
+unsafe impl<T, B> ArcLock<T, B> for arc2_weak::Arc<SpinLock<T, B>> {}
+unsafe impl<T, B> ArcLock<T, B> for arc3_optimized::Arc<SpinLock<T, B>> {}
+
+pub struct OwnedGuard<T, B = NoBackoff, P = Arc<SpinLock<T, B>>>
+where
+    P: ArcLock<T, B>,
+{
+    lock: P,
+    timer: HoldTimer,
+    _marker: PhantomData<fn() -> (T, B)>,
+}
+
+// Like the borrowing guard: the pointer itself is only Sync for a Send 'T', but the guard hands out '&T'.
+unsafe impl<T, B, P> Sync for OwnedGuard<T, B, P>
+where
+    T: Sync,
+    P: ArcLock<T, B> + Sync,
+{
+}
+
+impl<T, B, P: ArcLock<T, B>> OwnedGuard<T, B, P> {
+    // Takes over the lock from a borrowing guard.
+    fn new(lock: &P, guard: Guard<'_, T, B>) -> Self {
+        let timer = guard.timer;
+        mem::forget(guard);
+
+        Self {
+            lock: lock.clone(),
+            timer,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, B, P: ArcLock<T, B>> Deref for OwnedGuard<T, B, P> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T, B, P: ArcLock<T, B>> DerefMut for OwnedGuard<T, B, P> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T, B, P: ArcLock<T, B>> Drop for OwnedGuard<T, B, P> {
+    fn drop(&mut self) {
+        self.lock.state.unlock(self.timer);
     }
 }
 
 impl<T> SpinLock<T> {
     pub const fn new(value: T) -> Self {
+        Self::with_backoff(value)
+    }
+}
+
+impl<T, B: Backoff> SpinLock<T, B> {
+    pub const fn with_backoff(value: T) -> Self {
         Self {
-            locked: AtomicBool::new(false),
+            state: State {
+                locked: AtomicBool::new(false),
+                stats: Stats::new(),
+                id: LockId::new(),
+            },
             value: UnsafeCell::new(value),
+            _backoff: PhantomData,
+        }
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn lock(&self) -> Guard<'_, T, B> {
+        self.state.id.acquiring();
+
+        let mut backoff = B::default();
+        let mut spins = 0;
+        while self.state.locked.swap(true, Ordering::Acquire) {
+            backoff.backoff();
+            spins += 1;
         }
+
+        self.state.id.acquired();
+        self.guard(spins)
     }
 
-    pub fn lock(&self) -> Guard<T> {
-        while self.locked.swap(true, Ordering::Acquire) {
-            std::hint::spin_loop();
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn try_lock(&self) -> Option<Guard<'_, T, B>> {
+        if self.state.locked.swap(true, Ordering::Acquire) {
+            None
+        } else {
+            self.state.id.acquired();
+            Some(self.guard(0))
         }
+    }
+
+    //
+    // A timeout too large to add to the current time waits until a deadline that is far enough away to never be
+    //  reached, instead of falling back to 'lock' (which would take part in lock order checking).
+    //
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn try_lock_for(&self, timeout: Duration) -> Option<Guard<'_, T, B>> {
+        let now = Instant::now();
+        let deadline = now
+            .checked_add(timeout)
+            .unwrap_or_else(|| now + Duration::from_secs(u64::from(u32::MAX)));
 
-        Guard { lock: self }
+        self.try_lock_until(deadline)
+    }
+
+    //
+    // The first attempt is made regardless of the deadline, so a deadline in the past behaves like 'try_lock'. Spinning
+    //  on a relaxed load in between attempts keeps the cache line shared until the lock appears to be free.
+    //
+    // A timed attempt cannot deadlock, so it does not take part in lock order checking (but the lock does count as held).
+    //
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn try_lock_until(&self, deadline: Instant) -> Option<Guard<'_, T, B>> {
+        let mut backoff = B::default();
+        let mut spins = 0;
+        loop {
+            if !self.state.locked.swap(true, Ordering::Acquire) {
+                self.state.id.acquired();
+                return Some(self.guard(spins));
+            }
+
+            while self.state.locked.load(Ordering::Relaxed) {
+                if Instant::now() >= deadline {
+                    return None;
+                }
+
+                backoff.backoff();
+                spins += 1;
+            }
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.state.stats.snapshot()
+    }
+
+    // Must only be called with the lock held.
+    fn guard(&self, spins: u64) -> Guard<'_, T, B> {
+        Guard {
+            lock: self,
+            timer: self.state.stats.acquired(spins),
+        }
     }
 }
 
@@ -97,3 +416,94 @@ fn test_spin_lock() {
     let g = l.lock();
     drop(g); // Explicitly dropping the guard consumes it.
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn test_spin_lock_try_lock() {
+    let l = SpinLock::new(42);
+
+    {
+        let mut g = l.try_lock().unwrap();
+        *g = 23;
+        assert!(l.try_lock().is_none());
+        assert!(l.try_lock_for(Duration::from_millis(10)).is_none());
+        assert!(l.try_lock_until(Instant::now()).is_none());
+    }
+
+    assert_eq!(*l.try_lock_for(Duration::ZERO).unwrap(), 23);
+    assert_eq!(*l.try_lock_for(Duration::MAX).unwrap(), 23);
+    assert_eq!(*l.try_lock_until(Instant::now()).unwrap(), 23);
+
+    thread::scope(|s| {
+        let mut g = l.lock();
+
+        s.spawn(|| {
+            assert!(l.try_lock_for(Duration::from_millis(10)).is_none());
+        })
+        .join()
+        .unwrap();
+
+        s.spawn(|| {
+            let g = l.try_lock_for(Duration::from_secs(10)).unwrap();
+            assert_eq!(*g, 42);
+        });
+
+        thread::sleep(Duration::from_millis(50));
+        *g = 42;
+    });
+}
+
+#[test]
+fn test_spin_lock_map() {
+    let l = SpinLock::new((42, vec![1, 2, 3]));
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            let mut g = Guard::map(l.lock(), |(_, v)| v);
+            g.push(4);
+
+            let mut g = MappedGuard::try_map(g, |v| v.get_mut(0)).ok().unwrap();
+            *g = 0;
+
+            assert!(l.try_lock().is_none());
+        });
+    });
+
+    let g = Guard::try_map(l.lock(), |(_, v)| v.get_mut(4))
+        .err()
+        .unwrap();
+    assert_eq!(g.1, [0, 2, 3, 4]);
+    drop(g);
+
+    let g = Guard::map(l.lock(), |(n, _)| n);
+    assert_eq!(*g, 42);
+    assert!(l.try_lock().is_none());
+    drop(g);
+
+    assert!(l.try_lock().is_some());
+}
+
+#[test]
+fn test_spin_lock_owned() {
+    struct Job {
+        data: OwnedGuard<Vec<i32>>,
+    }
+
+    let l = Arc::new(SpinLock::new(Vec::new()));
+
+    let mut job = Job {
+        data: l.lock_owned(),
+    };
+    thread::spawn(move || job.data.push(1)).join().unwrap();
+
+    assert_eq!(*l.try_lock_owned().unwrap(), [1]);
+
+    let l = arc3_optimized::Arc::new(SpinLock::new(0));
+    let mut g = l.lock_owned();
+    *g += 1;
+    assert!(l.try_lock().is_none());
+    assert!(l.try_lock_owned().is_none());
+    drop(g);
+
+    assert_eq!(*l.lock(), 1);
+}
@@ -0,0 +1,275 @@
+#[cfg(feature = "std")]
+use core::cell::Cell;
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+use alloc::boxed::Box;
+
+use super::deadlock::LockId;
+#[cfg(feature = "stats")]
+use super::stats::LockStats;
+use super::stats::{HoldTimer, Stats};
+
+#[cfg(test)]
+use std::thread;
+
+///
+/// Pros:
+///   - Same safe, guarded interface as the guard-based spin lock.
+///   - Fair: threads acquire the lock in the order in which they started waiting for it (FIFO).
+///   - Every waiter spins on its own queue node, so a lock hand-over only touches the cache line of the next waiter.
+///
+/// Cons:
+///   - The first acquisition on every thread allocates a queue node (or every acquisition, without the 'std'
+///     feature).
+///   - Unlocking may have to wait for a thread that is halfway enqueueing itself.
+///
+/// Notes:
+///   - The lock only stores a pointer to the tail of an explicit queue of waiters. A thread enqueues itself by swapping
+///     its node into the tail, and linking it to the node of its predecessor (if any). It then spins on its own
+///     'locked' flag, which is cleared by its predecessor upon unlocking.
+///   - Once the lock is handed over (or the queue is empty), nobody refers to the node of the unlocking thread anymore.
+///     The guard keeps it in a thread local as a spare for the next acquisition on that thread, if there is no spare
+///     node yet (i.e. unless the thread holds several locks at once).
+///
+pub struct SpinLock<T> {
+    tail: AtomicPtr<Node>,
+    stats: Stats,
+    id: LockId,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for SpinLock<T> where T: Send {}
+
+// Aligned to a (typical) cache line, so that waiters spinning on neighbouring nodes do not interfere.
+#[repr(align(64))]
+struct Node {
+    next: AtomicPtr<Node>,
+    locked: AtomicBool,
+}
+
+#[cfg(feature = "std")]
+thread_local! {
+    static SPARE_NODE: Cell<Option<Box<Node>>> = const { Cell::new(None) };
+}
+
+impl Node {
+    fn new() -> Box<Node> {
+        Box::new(Node {
+            next: AtomicPtr::new(ptr::null_mut()),
+            locked: AtomicBool::new(true),
+        })
+    }
+
+    // Without the thread local (e.g. during thread local destruction) we fall back to allocating and freeing nodes.
+    #[cfg(feature = "std")]
+    fn acquire() -> NonNull<Node> {
+        let node = SPARE_NODE
+            .try_with(|spare| spare.take())
+            .ok()
+            .flatten()
+            .unwrap_or_else(Node::new);
+
+        node.next.store(ptr::null_mut(), Ordering::Relaxed);
+        node.locked.store(true, Ordering::Relaxed);
+        NonNull::from(Box::leak(node))
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn acquire() -> NonNull<Node> {
+        NonNull::from(Box::leak(Node::new()))
+    }
+
+    // Keeps the existing spare node, if any, and frees this one.
+    #[cfg(feature = "std")]
+    unsafe fn recycle(node: NonNull<Node>) {
+        let node = Box::from_raw(node.as_ptr());
+        let _ = SPARE_NODE.try_with(|spare| spare.set(Some(spare.take().unwrap_or(node))));
+    }
+
+    #[cfg(not(feature = "std"))]
+    unsafe fn recycle(node: NonNull<Node>) {
+        drop(Box::from_raw(node.as_ptr()));
+    }
+}
+
+pub struct Guard<'a, T> {
+    lock: &'a SpinLock<T>,
+    node: NonNull<Node>,
+    timer: HoldTimer,
+}
+
+// The node pointer makes the guard neither Send nor Sync, but the node is exclusively ours until the guard is dropped.
+unsafe impl<T> Send for Guard<'_, T> where T: Send {}
+unsafe impl<T> Sync for Guard<'_, T> where T: Sync {}
+
+impl<T> Deref for Guard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for Guard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for Guard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.id.released();
+        self.lock.stats.released(self.timer);
+
+        let node = unsafe { self.node.as_ref() };
+
+        let mut next = node.next.load(Ordering::Acquire);
+        if next.is_null() {
+            //
+            // No known successor: if we are still the tail, the queue becomes empty and we are done. Otherwise another
+            //  thread has swapped itself in, but has not linked itself to our node yet, so wait for it to do so.
+            //
+            if self
+                .lock
+                .tail
+                .compare_exchange(
+                    self.node.as_ptr(),
+                    ptr::null_mut(),
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+            {
+                unsafe { Node::recycle(self.node) };
+                return;
+            }
+
+            loop {
+                next = node.next.load(Ordering::Acquire);
+                if !next.is_null() {
+                    break;
+                }
+
+                core::hint::spin_loop();
+            }
+        }
+
+        //
+        // Hand the lock over to our successor. After this, nobody refers to our node anymore.
+        //
+        unsafe { (*next).locked.store(false, Ordering::Release) };
+        unsafe { Node::recycle(self.node) };
+    }
+}
+
+impl<T> SpinLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            tail: AtomicPtr::new(ptr::null_mut()),
+            stats: Stats::new(),
+            id: LockId::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    //
+    // The swap on the tail is acquire/release: acquire to see the initialized node of our predecessor (and to see the
+    //  data of the last thread that released the lock through an empty queue), release to publish our own node to our
+    //  successor.
+    //
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn lock(&self) -> Guard<'_, T> {
+        self.id.acquiring();
+
+        let node = Node::acquire();
+
+        let prev = self.tail.swap(node.as_ptr(), Ordering::AcqRel);
+        let mut spins = 0;
+        if !prev.is_null() {
+            unsafe { (*prev).next.store(node.as_ptr(), Ordering::Release) };
+
+            while unsafe { node.as_ref() }.locked.load(Ordering::Acquire) {
+                core::hint::spin_loop();
+                spins += 1;
+            }
+        }
+
+        self.id.acquired();
+
+        Guard {
+            lock: self,
+            node,
+            timer: self.stats.acquired(spins),
+        }
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn try_lock(&self) -> Option<Guard<'_, T>> {
+        if !self.tail.load(Ordering::Relaxed).is_null() {
+            return None;
+        }
+
+        let node = Node::acquire();
+
+        match self.tail.compare_exchange(
+            ptr::null_mut(),
+            node.as_ptr(),
+            Ordering::AcqRel,
+            Ordering::Relaxed,
+        ) {
+            Ok(_) => {
+                self.id.acquired();
+
+                Some(Guard {
+                    lock: self,
+                    node,
+                    timer: self.stats.acquired(0),
+                })
+            }
+            Err(_) => {
+                unsafe { Node::recycle(node) };
+                None
+            }
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.stats.snapshot()
+    }
+}
+
+#[test]
+fn test_spin_lock() {
+    let l = SpinLock::new(0);
+
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..100 {
+                    *l.lock() += 1;
+                }
+            });
+        }
+    });
+
+    let g = l.try_lock().unwrap();
+    assert_eq!(*g, 400);
+    assert!(l.try_lock().is_none());
+    drop(g);
+
+    assert!(l.tail.load(Ordering::Relaxed).is_null());
+
+    // The node of the unlocking thread is recycled for the next acquisition on this thread.
+    #[cfg(feature = "std")]
+    {
+        let g = l.lock();
+        let node = g.node;
+        drop(g);
+
+        assert_eq!(l.lock().node, node);
+    }
+}
@@ -0,0 +1,190 @@
+use std::cell::UnsafeCell;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LockResult, PoisonError, TryLockError, TryLockResult};
+use std::thread;
+
+use super::deadlock::LockId;
+#[cfg(feature = "stats")]
+use super::stats::LockStats;
+use super::stats::{HoldTimer, Stats};
+
+///
+/// Pros:
+///   - Same safe, guarded interface as the guard-based spin lock.
+///   - A panic while holding the lock poisons it, so other threads are told the value may be half-updated.
+///
+/// Cons:
+///   - Every access has to deal with the 'LockResult', just like with 'std::sync::Mutex'.
+///
+/// Notes:
+///   - The guard remembers whether the thread was already panicking when the lock was taken. Only a panic that starts
+///     while the lock is held poisons it, not a guard that is taken and dropped during unwinding.
+///   - The poison flag is only written while holding the lock, so a relaxed store is enough: the release store on
+///     unlocking makes it visible to the next thread taking the lock.
+///
+pub struct SpinLock<T> {
+    locked: AtomicBool,
+    poisoned: AtomicBool,
+    stats: Stats,
+    id: LockId,
+    value: UnsafeCell<T>,
+}
+
+unsafe impl<T> Sync for SpinLock<T> where T: Send {}
+
+pub struct Guard<'a, T> {
+    lock: &'a SpinLock<T>,
+    panicking: bool,
+    timer: HoldTimer,
+}
+
+unsafe impl<T> Sync for Guard<'_, T> where T: Sync {}
+
+impl<T> Deref for Guard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for Guard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for Guard<'_, T> {
+    fn drop(&mut self) {
+        if !self.panicking && thread::panicking() {
+            self.lock.poisoned.store(true, Ordering::Relaxed);
+        }
+
+        self.lock.id.released();
+        self.lock.stats.released(self.timer);
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+impl<T> SpinLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            poisoned: AtomicBool::new(false),
+            stats: Stats::new(),
+            id: LockId::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn lock(&self) -> LockResult<Guard<'_, T>> {
+        self.id.acquiring();
+
+        let mut spins = 0;
+        while self.locked.swap(true, Ordering::Acquire) {
+            std::hint::spin_loop();
+            spins += 1;
+        }
+
+        self.id.acquired();
+        self.guard(spins)
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn try_lock(&self) -> TryLockResult<Guard<'_, T>> {
+        if self.locked.swap(true, Ordering::Acquire) {
+            return Err(TryLockError::WouldBlock);
+        }
+
+        self.id.acquired();
+
+        Ok(self.guard(0)?)
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Relaxed)
+    }
+
+    pub fn clear_poison(&self) {
+        self.poisoned.store(false, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.stats.snapshot()
+    }
+
+    pub fn get_mut(&mut self) -> LockResult<&mut T> {
+        let poisoned = *self.poisoned.get_mut();
+        let value = self.value.get_mut();
+
+        if poisoned {
+            Err(PoisonError::new(value))
+        } else {
+            Ok(value)
+        }
+    }
+
+    pub fn into_inner(self) -> LockResult<T> {
+        let poisoned = self.poisoned.into_inner();
+        let value = self.value.into_inner();
+
+        if poisoned {
+            Err(PoisonError::new(value))
+        } else {
+            Ok(value)
+        }
+    }
+
+    // Must only be called with the lock held.
+    fn guard(&self, spins: u64) -> LockResult<Guard<'_, T>> {
+        let guard = Guard {
+            lock: self,
+            panicking: thread::panicking(),
+            timer: self.stats.acquired(spins),
+        };
+
+        if self.poisoned.load(Ordering::Relaxed) {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
+    }
+}
+
+#[test]
+fn test_spin_lock() {
+    let l = SpinLock::new(42);
+
+    thread::scope(|s| {
+        let t = s.spawn(|| {
+            let mut g = l.lock().unwrap();
+            *g = 23;
+            panic!("oops");
+        });
+
+        assert!(t.join().is_err());
+    });
+
+    assert!(l.is_poisoned());
+    assert!(matches!(l.try_lock(), Err(TryLockError::Poisoned(_))));
+
+    {
+        let mut g = l.lock().err().unwrap().into_inner();
+        assert_eq!(*g, 23);
+        *g = 42;
+    }
+
+    l.clear_poison();
+    assert_eq!(*l.lock().unwrap(), 42);
+
+    {
+        let g = l.lock().unwrap();
+        assert!(matches!(l.try_lock(), Err(TryLockError::WouldBlock)));
+        drop(g);
+    }
+
+    assert_eq!(l.into_inner().unwrap(), 42);
+}
@@ -0,0 +1,183 @@
+use std::cell::UnsafeCell;
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::deadlock::LockId;
+#[cfg(feature = "stats")]
+use super::stats::LockStats;
+use super::stats::Stats;
+
+#[cfg(test)]
+use std::{cell::Cell, thread, time::Duration};
+
+//
+// A unique, non-zero identifier for every thread. The address of a thread local is not good enough, as it may be reused
+//  by a new thread after the old one exited.
+//
+static NEXT_THREAD_ID: AtomicUsize = AtomicUsize::new(1);
+
+thread_local! {
+    static THREAD_ID: usize = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+fn current_thread_id() -> usize {
+    THREAD_ID.with(|id| *id)
+}
+
+///
+/// Pros:
+///   - The thread holding the lock can lock it again, without deadlocking on itself.
+///
+/// Cons:
+///   - Only shared access to the value: with nested guards, a '&mut T' from one guard would alias the '&T' of another.
+///     Use interior mutability (e.g. 'Cell' or 'RefCell') to modify the value.
+///   - Guards are not Send, they must be dropped on the thread that owns the lock.
+///
+/// Notes:
+///   - The recursion count is only ever touched by the owning thread, so it does not have to be atomic.
+///   - Checking for ownership can be a relaxed load: only the current thread ever stores its own id, so if we see it,
+///     we must have stored it ourselves.
+///
+pub struct ReentrantSpinLock<T> {
+    owner: AtomicUsize,
+    count: UnsafeCell<usize>,
+    stats: Stats,
+    id: LockId,
+    value: T,
+}
+
+// Only one thread at a time has access to the value, so like with the other spin locks, 'T' only has to be Send.
+unsafe impl<T> Sync for ReentrantSpinLock<T> where T: Send {}
+
+pub struct Guard<'a, T> {
+    lock: &'a ReentrantSpinLock<T>,
+    _no_send: PhantomData<*const ()>,
+}
+
+unsafe impl<T> Sync for Guard<'_, T> where T: Sync {}
+
+impl<T> Deref for Guard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.lock.value
+    }
+}
+
+impl<T> Drop for Guard<'_, T> {
+    fn drop(&mut self) {
+        let count = unsafe { &mut *self.lock.count.get() };
+
+        *count -= 1;
+        if *count == 0 {
+            self.lock.id.released();
+            self.lock.stats.released_unguarded();
+            self.lock.owner.store(0, Ordering::Release);
+        }
+    }
+}
+
+impl<T> ReentrantSpinLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            owner: AtomicUsize::new(0),
+            count: UnsafeCell::new(0),
+            stats: Stats::new(),
+            id: LockId::new(),
+            value,
+        }
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn lock(&self) -> Guard<'_, T> {
+        let id = current_thread_id();
+
+        // Only the outermost acquisition can block, locking again is not a lock order inversion.
+        if self.owner.load(Ordering::Relaxed) != id {
+            self.id.acquiring();
+
+            let mut spins = 0;
+            while self
+                .owner
+                .compare_exchange_weak(0, id, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                std::hint::spin_loop();
+                spins += 1;
+            }
+
+            self.id.acquired();
+            self.stats.acquired_unguarded(spins);
+        }
+
+        unsafe { self.enter() }
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn try_lock(&self) -> Option<Guard<'_, T>> {
+        let id = current_thread_id();
+
+        if self.owner.load(Ordering::Relaxed) != id {
+            if self
+                .owner
+                .compare_exchange(0, id, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                return None;
+            }
+
+            self.id.acquired();
+            self.stats.acquired_unguarded(0);
+        }
+
+        Some(unsafe { self.enter() })
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.stats.snapshot()
+    }
+
+    /// # Safety
+    /// The current thread must own the lock.
+    unsafe fn enter(&self) -> Guard<'_, T> {
+        let count = &mut *self.count.get();
+        *count = count.checked_add(1).expect("lock count overflow");
+
+        Guard {
+            lock: self,
+            _no_send: PhantomData,
+        }
+    }
+}
+
+#[test]
+fn test_reentrant_spin_lock() {
+    let l = ReentrantSpinLock::new(Cell::new(0));
+
+    thread::scope(|s| {
+        let g1 = l.lock();
+        let g2 = l.lock();
+        let g3 = l.try_lock().unwrap();
+        g3.set(1);
+        drop(g3);
+        drop(g2);
+
+        s.spawn(|| {
+            assert!(l.try_lock().is_none());
+        })
+        .join()
+        .unwrap();
+
+        s.spawn(|| {
+            let g = l.lock();
+            assert_eq!(g.get(), 2);
+        });
+
+        thread::sleep(Duration::from_millis(50));
+        g1.set(g1.get() + 1);
+    });
+
+    assert_eq!(l.owner.load(Ordering::Relaxed), 0);
+}
@@ -1,4 +1,11 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::backoff::{Backoff, NoBackoff};
+use super::raw_lock::RawLock;
+#[cfg(feature = "stats")]
+use super::stats::LockStats;
+use super::stats::Stats;
 
 #[cfg(test)]
 use std::{thread, time::Duration};
@@ -12,35 +19,91 @@ use std::{thread, time::Duration};
 ///
 /// Notes:
 ///   - By using release memory ordering on the store in 'unlock', and acquire memory ordering in the load part of the
-///      swap operation in 'lock', we assure there is a happens-before relation on 'lock'/'unlock'.
+///     swap operation in 'lock', we assure there is a happens-before relation on 'lock'/'unlock'.
 ///
-
-pub struct SpinLock {
+pub struct SpinLock<B = NoBackoff> {
     locked: AtomicBool,
+    stats: Stats,
+    _backoff: PhantomData<fn() -> B>,
 }
 
 impl SpinLock {
     pub const fn new() -> Self {
+        Self::with_backoff()
+    }
+}
+
+impl<B: Backoff> SpinLock<B> {
+    pub const fn with_backoff() -> Self {
         Self {
             locked: AtomicBool::new(false),
+            stats: Stats::new(),
+            _backoff: PhantomData,
         }
     }
 
     pub fn lock(&self) {
+        let mut backoff = B::default();
+        let mut spins = 0;
         while self.locked.swap(true, Ordering::Acquire) {
-            std::hint::spin_loop();
+            backoff.backoff();
+            spins += 1;
         }
 
         // Also fine, and almost identical:
         //
         //while self.locked.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_ok() {
-        //    std::hint::spin_loop();
+        //    core::hint::spin_loop();
         //}
+
+        self.stats.acquired_unguarded(spins);
+    }
+
+    pub fn try_lock(&self) -> bool {
+        if self.locked.swap(true, Ordering::Acquire) {
+            return false;
+        }
+
+        self.stats.acquired_unguarded(0);
+        true
     }
 
     pub fn unlock(&self) {
+        self.stats.released_unguarded();
         self.locked.store(false, Ordering::Release);
     }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.stats.snapshot()
+    }
+}
+
+unsafe impl<B: Backoff> RawLock for SpinLock<B> {
+    const INIT: Self = Self::with_backoff();
+
+    fn lock(&self) {
+        self.lock();
+    }
+
+    fn try_lock(&self) -> bool {
+        self.try_lock()
+    }
+
+    unsafe fn unlock(&self) {
+        self.unlock();
+    }
+
+    #[cfg(feature = "stats")]
+    fn stats(&self) -> LockStats {
+        self.stats()
+    }
+}
+
+impl<B: Backoff> Default for SpinLock<B> {
+    fn default() -> Self {
+        Self::with_backoff()
+    }
 }
 
 #[test]
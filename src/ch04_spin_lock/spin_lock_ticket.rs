@@ -0,0 +1,161 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use super::raw_lock::{self, Lock, RawLock};
+#[cfg(feature = "stats")]
+use super::stats::LockStats;
+use super::stats::Stats;
+
+#[cfg(test)]
+use std::thread;
+
+///
+/// Pros:
+///   - Same safe, guarded interface as the guard-based spin lock, by plugging the raw ticket lock into 'Lock'.
+///   - Fair: threads acquire the lock in the order in which they started waiting for it (FIFO).
+///
+/// Cons:
+///   - All waiters still spin on the same cache line ('now_serving').
+///   - A waiting thread that is descheduled holds up all threads queued behind it.
+///
+/// Notes:
+///   - Like taking a number at the butcher's counter: 'lock' draws the next ticket and waits until it is served. The
+///     unlocking thread hands the lock over to the next ticket in line.
+///   - The counters wrap around, which is fine as long as there are less than 2^32 threads waiting at the same time.
+///
+pub struct RawSpinLock {
+    next_ticket: AtomicU32,
+    now_serving: AtomicU32,
+    stats: Stats,
+}
+
+pub type SpinLock<T> = Lock<RawSpinLock, T>;
+
+pub type Guard<'a, T> = raw_lock::Guard<'a, RawSpinLock, T>;
+
+impl RawSpinLock {
+    pub const fn new() -> Self {
+        Self {
+            next_ticket: AtomicU32::new(0),
+            now_serving: AtomicU32::new(0),
+            stats: Stats::new(),
+        }
+    }
+
+    //
+    // Drawing a ticket can be relaxed, the ticket number is not used to access any data. The acquire load on
+    //  'now_serving' pairs with the release increment in 'unlock' of the previous ticket holder.
+    //
+    pub fn lock(&self) {
+        let ticket = self.next_ticket.fetch_add(1, Ordering::Relaxed);
+        let mut spins = 0;
+        while self.now_serving.load(Ordering::Acquire) != ticket {
+            core::hint::spin_loop();
+            spins += 1;
+        }
+
+        self.stats.acquired_unguarded(spins);
+    }
+
+    //
+    // Only draw a ticket if it would be served immediately, i.e. nobody holds or waits for the lock.
+    //
+    pub fn try_lock(&self) -> bool {
+        let serving = self.now_serving.load(Ordering::Acquire);
+        let locked = self
+            .next_ticket
+            .compare_exchange(
+                serving,
+                serving.wrapping_add(1),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .is_ok();
+
+        if locked {
+            self.stats.acquired_unguarded(0);
+        }
+
+        locked
+    }
+
+    /// # Safety
+    /// The lock must be held by the current context, i.e. the ticket being served must be ours.
+    pub unsafe fn unlock(&self) {
+        self.stats.released_unguarded();
+        self.now_serving.fetch_add(1, Ordering::Release);
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.stats.snapshot()
+    }
+}
+
+impl Default for RawSpinLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl RawLock for RawSpinLock {
+    const INIT: Self = Self::new();
+
+    fn lock(&self) {
+        self.lock();
+    }
+
+    fn try_lock(&self) -> bool {
+        self.try_lock()
+    }
+
+    unsafe fn unlock(&self) {
+        self.unlock();
+    }
+
+    #[cfg(feature = "stats")]
+    fn stats(&self) -> LockStats {
+        self.stats()
+    }
+}
+
+#[test]
+fn test_spin_lock() {
+    let l = SpinLock::new(Vec::new());
+
+    thread::scope(|s| {
+        let g = l.lock();
+        assert!(l.try_lock().is_none());
+
+        //
+        // Spawn the waiters one by one, each after the previous one has drawn its ticket. Once the lock is released
+        //  they must acquire it in the order in which they were spawned.
+        //
+        for i in 0..4 {
+            let l = &l;
+            s.spawn(move || {
+                l.lock().push(i);
+            });
+
+            while l.raw.next_ticket.load(Ordering::Relaxed) != i + 2 {
+                thread::yield_now();
+            }
+        }
+
+        drop(g);
+    });
+
+    assert_eq!(*l.try_lock().unwrap(), [0, 1, 2, 3]);
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn test_spin_lock_stats() {
+    let l = SpinLock::new(0);
+
+    *l.lock() += 1;
+    assert!(l.try_lock().is_some());
+
+    let stats = l.stats();
+    assert_eq!(stats.acquisitions, 2);
+    assert_eq!(stats.contended, 0);
+}
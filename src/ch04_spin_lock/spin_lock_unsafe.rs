@@ -1,5 +1,11 @@
-use std::cell::UnsafeCell;
-use std::sync::atomic::{AtomicBool, Ordering};
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::backoff::{Backoff, NoBackoff};
+#[cfg(feature = "stats")]
+use super::stats::LockStats;
+use super::stats::Stats;
 
 #[cfg(test)]
 use std::{thread, time::Duration};
@@ -11,10 +17,11 @@ use std::{thread, time::Duration};
 /// Cons:
 ///   - Unsafe interface.
 ///
-
-pub struct SpinLock<T> {
+pub struct SpinLock<T, B = NoBackoff> {
     locked: AtomicBool,
+    stats: Stats,
     value: UnsafeCell<T>,
+    _backoff: PhantomData<fn() -> B>,
 }
 
 //
@@ -24,29 +31,49 @@ pub struct SpinLock<T> {
 //  be transferred to another thread context), and the access to the UnsafeCell is synchronized using the atomic bool,
 //  we can assume all is fine. The unsafe interface is a nuisance, but that's all.
 //
-unsafe impl<T> Sync for SpinLock<T> where T: Send {}
+unsafe impl<T, B> Sync for SpinLock<T, B> where T: Send {}
 
 impl<T> SpinLock<T> {
     pub const fn new(value: T) -> Self {
+        Self::with_backoff(value)
+    }
+}
+
+impl<T, B: Backoff> SpinLock<T, B> {
+    pub const fn with_backoff(value: T) -> Self {
         Self {
             locked: AtomicBool::new(false),
+            stats: Stats::new(),
             value: UnsafeCell::new(value),
+            _backoff: PhantomData,
         }
     }
 
+    #[allow(clippy::mut_from_ref)]
     pub fn lock(&self) -> &mut T {
+        let mut backoff = B::default();
+        let mut spins = 0;
         while self.locked.swap(true, Ordering::Acquire) {
-            std::hint::spin_loop();
+            backoff.backoff();
+            spins += 1;
         }
 
+        self.stats.acquired_unguarded(spins);
+
         unsafe { &mut *self.value.get() }
     }
 
     /// # Safety
     /// This function is unsafe, as there may be references to self.value outside of the critical section.
     pub unsafe fn unlock(&self) {
+        self.stats.released_unguarded();
         self.locked.store(false, Ordering::Release);
     }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.stats.snapshot()
+    }
 }
 
 #[test]
@@ -0,0 +1,450 @@
+use core::cell::UnsafeCell;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use super::deadlock::LockId;
+#[cfg(feature = "stats")]
+use super::stats::LockStats;
+use super::stats::{HoldTimer, Stats};
+
+#[cfg(all(test, feature = "deadlock-detection"))]
+use super::spin_lock_guard::SpinLock;
+#[cfg(test)]
+use std::{thread, time::Duration};
+
+//
+// The lock state is a single atomic word:
+//
+//   - bit 0: WRITER         -- write locked.
+//   - bit 1: UPGRADABLE     -- held by an upgradable reader, which coexists with readers but excludes writers.
+//   - bit 2: WRITER_WAITING -- a writer is waiting for the readers to leave, new readers are held off.
+//   - rest:  the number of active readers, in units of READER.
+//
+const WRITER: u32 = 1;
+const UPGRADABLE: u32 = 2;
+const WRITER_WAITING: u32 = 4;
+const READER: u32 = 8;
+
+///
+/// Pros:
+///   - Any number of readers can access the value at the same time.
+///   - Writers do not starve: once a writer is waiting, new readers have to wait until it is done.
+///   - Read, decide, then maybe write: an upgradable reader shares the value with readers, and can be upgraded to a
+///     writer without letting any other writer in between.
+///
+/// Cons:
+///   - Readers can starve under a constant stream of writers.
+///   - All readers and writers modify the same state word, so even read locking bounces its cache line around.
+///   - Only one upgradable reader at a time, otherwise two of them could wait on each other to upgrade.
+///
+/// Notes:
+///   - Deadlock detection treats a read lock as shared: taking a read lock that the thread already holds (in any
+///     mode) is not reported, even though a waiting writer makes that deadlock. Converting a guard keeps the lock held.
+///
+pub struct SpinRwLock<T> {
+    state: AtomicU32,
+    stats: Stats,
+    id: LockId,
+    value: UnsafeCell<T>,
+}
+
+// Readers on different threads share '&T', so on top of Send (for writers), 'T' must be Sync as well.
+unsafe impl<T> Sync for SpinRwLock<T> where T: Send + Sync {}
+
+pub struct ReadGuard<'a, T> {
+    lock: &'a SpinRwLock<T>,
+    timer: HoldTimer,
+}
+
+pub struct UpgradableReadGuard<'a, T> {
+    lock: &'a SpinRwLock<T>,
+    timer: HoldTimer,
+}
+
+pub struct WriteGuard<'a, T> {
+    lock: &'a SpinRwLock<T>,
+    timer: HoldTimer,
+}
+
+impl<T> Deref for ReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for ReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.id.released();
+        self.lock.stats.released(self.timer);
+        self.lock.state.fetch_sub(READER, Ordering::Release);
+    }
+}
+
+impl<T> Deref for UpgradableReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for UpgradableReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.id.released();
+        self.lock.stats.released(self.timer);
+        self.lock.state.fetch_sub(UPGRADABLE, Ordering::Release);
+    }
+}
+
+//
+// Like mapping on the guard-based spin lock, converting guards is done through associated functions, so that they do
+//  not shadow methods of 'T' with the same name. The hold time keeps running across conversions.
+//
+impl<'a, T> UpgradableReadGuard<'a, T> {
+    //
+    // Holding the upgradable bit keeps other writers (and upgradable readers) out, so we only have to wait for the
+    //  readers to leave. Meanwhile, the waiting bit holds off new readers.
+    //
+    pub fn upgrade(guard: Self) -> WriteGuard<'a, T> {
+        let (lock, timer) = (guard.lock, guard.timer);
+        mem::forget(guard);
+
+        let mut s = lock.state.load(Ordering::Relaxed);
+        loop {
+            if s & !(UPGRADABLE | WRITER_WAITING) == 0 {
+                match lock.state.compare_exchange_weak(
+                    s,
+                    WRITER,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => return WriteGuard { lock, timer },
+                    Err(e) => {
+                        s = e;
+                        continue;
+                    }
+                }
+            }
+
+            if s & WRITER_WAITING == 0 {
+                lock.state.fetch_or(WRITER_WAITING, Ordering::Relaxed);
+            }
+
+            core::hint::spin_loop();
+            s = lock.state.load(Ordering::Relaxed);
+        }
+    }
+
+    pub fn try_upgrade(guard: Self) -> Result<WriteGuard<'a, T>, Self> {
+        let s = guard.lock.state.load(Ordering::Relaxed);
+        if s & !(UPGRADABLE | WRITER_WAITING) != 0
+            || guard
+                .lock
+                .state
+                .compare_exchange(s, WRITER, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+        {
+            return Err(guard);
+        }
+
+        let (lock, timer) = (guard.lock, guard.timer);
+        mem::forget(guard);
+
+        Ok(WriteGuard { lock, timer })
+    }
+
+    // Adding a reader and removing the upgradable bit in a single step, so no writer can get in between.
+    pub fn downgrade(guard: Self) -> ReadGuard<'a, T> {
+        let (lock, timer) = (guard.lock, guard.timer);
+        mem::forget(guard);
+
+        lock.state.fetch_add(READER - UPGRADABLE, Ordering::Release);
+
+        ReadGuard { lock, timer }
+    }
+}
+
+impl<T> Deref for WriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for WriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for WriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.id.released();
+        self.lock.stats.released(self.timer);
+
+        //
+        // Only clear the writer bit: if another writer flagged itself as waiting in the meantime, it goes before any new
+        //  readers.
+        //
+        self.lock.state.fetch_and(!WRITER, Ordering::Release);
+    }
+}
+
+impl<'a, T> WriteGuard<'a, T> {
+    // The writer bit is set, so adding 'READER - WRITER' clears it while adding a reader.
+    pub fn downgrade(guard: Self) -> ReadGuard<'a, T> {
+        let (lock, timer) = (guard.lock, guard.timer);
+        mem::forget(guard);
+
+        lock.state.fetch_add(READER - WRITER, Ordering::Release);
+
+        ReadGuard { lock, timer }
+    }
+
+    pub fn downgrade_to_upgradable(guard: Self) -> UpgradableReadGuard<'a, T> {
+        let (lock, timer) = (guard.lock, guard.timer);
+        mem::forget(guard);
+
+        lock.state.fetch_add(UPGRADABLE - WRITER, Ordering::Release);
+
+        UpgradableReadGuard { lock, timer }
+    }
+}
+
+impl<T> SpinRwLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            state: AtomicU32::new(0),
+            stats: Stats::new(),
+            id: LockId::new(),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn read(&self) -> ReadGuard<'_, T> {
+        self.id.acquiring_shared();
+
+        let mut spins = 0;
+        let mut s = self.state.load(Ordering::Relaxed);
+        loop {
+            if s & (WRITER | WRITER_WAITING) == 0 {
+                assert!(s < u32::MAX - READER, "too many readers");
+
+                match self.state.compare_exchange_weak(
+                    s,
+                    s + READER,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        self.id.acquired();
+                        return ReadGuard {
+                            lock: self,
+                            timer: self.stats.acquired(spins),
+                        };
+                    }
+                    Err(e) => {
+                        s = e;
+                        continue;
+                    }
+                }
+            }
+
+            core::hint::spin_loop();
+            spins += 1;
+            s = self.state.load(Ordering::Relaxed);
+        }
+    }
+
+    //
+    // An upgradable reader is held off by a waiting writer just like a reader, otherwise it could keep the writer
+    //  waiting forever by upgrading.
+    //
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn upgradable_read(&self) -> UpgradableReadGuard<'_, T> {
+        self.id.acquiring();
+
+        let mut spins = 0;
+        let mut s = self.state.load(Ordering::Relaxed);
+        loop {
+            if s & (WRITER | UPGRADABLE | WRITER_WAITING) == 0 {
+                match self.state.compare_exchange_weak(
+                    s,
+                    s | UPGRADABLE,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        self.id.acquired();
+                        return UpgradableReadGuard {
+                            lock: self,
+                            timer: self.stats.acquired(spins),
+                        };
+                    }
+                    Err(e) => {
+                        s = e;
+                        continue;
+                    }
+                }
+            }
+
+            core::hint::spin_loop();
+            spins += 1;
+            s = self.state.load(Ordering::Relaxed);
+        }
+    }
+
+    //
+    // Taking the write lock clears the waiting bit. Other waiting writers just set it again, before any reader can get
+    //  in (readers cannot get in while the writer bit is set).
+    //
+    #[cfg_attr(feature = "deadlock-detection", track_caller)]
+    pub fn write(&self) -> WriteGuard<'_, T> {
+        self.id.acquiring();
+
+        let mut spins = 0;
+        let mut s = self.state.load(Ordering::Relaxed);
+        loop {
+            if s & !WRITER_WAITING == 0 {
+                match self.state.compare_exchange_weak(
+                    s,
+                    WRITER,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        self.id.acquired();
+                        return WriteGuard {
+                            lock: self,
+                            timer: self.stats.acquired(spins),
+                        };
+                    }
+                    Err(e) => {
+                        s = e;
+                        continue;
+                    }
+                }
+            }
+
+            if s & WRITER_WAITING == 0 {
+                self.state.fetch_or(WRITER_WAITING, Ordering::Relaxed);
+            }
+
+            core::hint::spin_loop();
+            spins += 1;
+            s = self.state.load(Ordering::Relaxed);
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> LockStats {
+        self.stats.snapshot()
+    }
+}
+
+#[test]
+fn test_spin_rwlock() {
+    let l = SpinRwLock::new(0);
+
+    thread::scope(|s| {
+        let r1 = l.read();
+        let r2 = l.read();
+        assert_eq!(*r1 + *r2, 0);
+        drop(r2);
+
+        s.spawn(|| {
+            *l.write() = 1;
+        });
+
+        while l.state.load(Ordering::Relaxed) & WRITER_WAITING == 0 {
+            thread::yield_now();
+        }
+
+        //
+        // The waiting writer holds off new readers, so this reader can only get in after the writer is done.
+        //
+        s.spawn(|| {
+            assert_eq!(*l.read(), 1);
+        });
+
+        thread::sleep(Duration::from_millis(50));
+        drop(r1);
+    });
+
+    assert_eq!(l.state.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn test_spin_rwlock_upgradable() {
+    let l = SpinRwLock::new(0);
+
+    thread::scope(|s| {
+        let u = l.upgradable_read();
+        let r = l.read();
+        assert_eq!(*u + *r, 0);
+
+        s.spawn(|| {
+            *l.write() += 1;
+        });
+
+        //
+        // The upgrade waits for the reader to leave, and the writer above cannot get in before it: it only gets the lock
+        //  once the upgraded guard is dropped.
+        //
+        let u = UpgradableReadGuard::try_upgrade(u).err().unwrap();
+        s.spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            drop(r);
+        });
+
+        let mut w = UpgradableReadGuard::upgrade(u);
+        assert_eq!(*w, 0);
+        *w = 10;
+
+        let r = WriteGuard::downgrade(w);
+        assert_eq!(*r, 10);
+    });
+
+    assert_eq!(*l.read(), 11);
+
+    let u = WriteGuard::downgrade_to_upgradable(l.write());
+    let r = UpgradableReadGuard::downgrade(u);
+    assert_eq!(*r, 11);
+    drop(r);
+
+    assert_eq!(l.state.load(Ordering::Relaxed), 0);
+}
+
+#[cfg(feature = "deadlock-detection")]
+#[test]
+fn test_spin_rwlock_deadlock_detection() {
+    let l = SpinRwLock::new(0);
+    let m = SpinLock::new(0);
+
+    {
+        let _w = l.write();
+        let _g = m.lock();
+    }
+
+    thread::scope(|s| {
+        let t = s.spawn(|| {
+            let _g = m.lock();
+            let _r = l.read();
+        });
+
+        assert!(t.join().is_err());
+    });
+
+    // Nested reads are fine, and the lock is free again once all guards are gone.
+    let r1 = l.read();
+    let r2 = l.read();
+    drop((r1, r2));
+
+    let _u = l.upgradable_read();
+    let _g = m.lock();
+}
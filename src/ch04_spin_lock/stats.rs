@@ -0,0 +1,167 @@
+#[cfg(feature = "stats")]
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+#[cfg(all(test, feature = "stats"))]
+use super::spin_lock_guard::SpinLock;
+#[cfg(all(test, feature = "stats"))]
+use std::thread;
+
+//
+// Contention statistics, kept by every lock in this module when the 'stats' feature is enabled. Without the feature,
+//  'Stats' and 'HoldTimer' are zero-sized and all of their functions are empty, so they compile down to nothing.
+//
+// All counters are relaxed: they are independent of each other, and a snapshot is never used to access any data.
+//
+
+/// Snapshot of the contention statistics of a lock.
+#[cfg(feature = "stats")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LockStats {
+    /// The number of times the lock was taken.
+    pub acquisitions: u64,
+    /// The number of times the lock was taken after waiting for it.
+    pub contended: u64,
+    /// The total number of spin (or backoff) iterations over all acquisitions.
+    pub spins: u64,
+    /// The longest time the lock was held.
+    pub longest_hold: Duration,
+}
+
+pub(crate) struct Stats {
+    #[cfg(feature = "stats")]
+    acquisitions: AtomicU64,
+    #[cfg(feature = "stats")]
+    contended: AtomicU64,
+    #[cfg(feature = "stats")]
+    spins: AtomicU64,
+    #[cfg(feature = "stats")]
+    longest_hold: AtomicU64,
+    #[cfg(feature = "stats")]
+    hold_start: AtomicU64,
+}
+
+//
+// The moment a lock was taken, in nanoseconds since an arbitrary (process wide) epoch. Guards keep their own timer,
+//  locks without a guard store it in 'Stats::hold_start' instead.
+//
+#[derive(Clone, Copy)]
+pub(crate) struct HoldTimer {
+    #[cfg(feature = "stats")]
+    start: u64,
+}
+
+#[cfg(feature = "stats")]
+fn now() -> u64 {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+    EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+}
+
+#[cfg(feature = "stats")]
+impl Stats {
+    pub(crate) const fn new() -> Self {
+        Self {
+            acquisitions: AtomicU64::new(0),
+            contended: AtomicU64::new(0),
+            spins: AtomicU64::new(0),
+            longest_hold: AtomicU64::new(0),
+            hold_start: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn acquired(&self, spins: u64) -> HoldTimer {
+        self.acquisitions.fetch_add(1, Ordering::Relaxed);
+
+        if spins > 0 {
+            self.contended.fetch_add(1, Ordering::Relaxed);
+            self.spins.fetch_add(spins, Ordering::Relaxed);
+        }
+
+        HoldTimer { start: now() }
+    }
+
+    pub(crate) fn released(&self, timer: HoldTimer) {
+        self.longest_hold
+            .fetch_max(now().saturating_sub(timer.start), Ordering::Relaxed);
+    }
+
+    pub(crate) fn acquired_unguarded(&self, spins: u64) {
+        let timer = self.acquired(spins);
+        self.hold_start.store(timer.start, Ordering::Relaxed);
+    }
+
+    pub(crate) fn released_unguarded(&self) {
+        self.released(HoldTimer {
+            start: self.hold_start.load(Ordering::Relaxed),
+        });
+    }
+
+    pub(crate) fn snapshot(&self) -> LockStats {
+        LockStats {
+            acquisitions: self.acquisitions.load(Ordering::Relaxed),
+            contended: self.contended.load(Ordering::Relaxed),
+            spins: self.spins.load(Ordering::Relaxed),
+            longest_hold: Duration::from_nanos(self.longest_hold.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[cfg(not(feature = "stats"))]
+impl Stats {
+    pub(crate) const fn new() -> Self {
+        Self {}
+    }
+
+    #[inline(always)]
+    pub(crate) fn acquired(&self, _spins: u64) -> HoldTimer {
+        HoldTimer {}
+    }
+
+    #[inline(always)]
+    pub(crate) fn released(&self, _timer: HoldTimer) {}
+
+    #[inline(always)]
+    pub(crate) fn acquired_unguarded(&self, _spins: u64) {}
+
+    #[inline(always)]
+    pub(crate) fn released_unguarded(&self) {}
+}
+
+#[cfg(feature = "stats")]
+#[test]
+fn test_stats() {
+    let l = SpinLock::new(0);
+
+    {
+        let _g = l.lock();
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let stats = l.stats();
+    assert_eq!(stats.acquisitions, 1);
+    assert_eq!(stats.contended, 0);
+    assert_eq!(stats.spins, 0);
+    assert!(stats.longest_hold >= Duration::from_millis(10));
+
+    thread::scope(|s| {
+        let g = l.lock();
+
+        s.spawn(|| {
+            *l.lock() += 1;
+        });
+
+        thread::sleep(Duration::from_millis(10));
+        drop(g);
+    });
+
+    let stats = l.stats();
+    assert_eq!(stats.acquisitions, 3);
+    assert_eq!(stats.contended, 1);
+    assert!(stats.spins > 0);
+}
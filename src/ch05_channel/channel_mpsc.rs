@@ -0,0 +1,333 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+use std::thread;
+
+//
+// Like 'channel_simple', but with handles: any number of senders and a single receiver. Every handle is counted, so
+//  the receiver finds out when all senders are gone (instead of waiting forever), and the senders find out when the
+//  receiver is gone (instead of filling up a queue nobody reads).
+//
+// A bounded channel applies backpressure: senders wait (on a second condition variable) while the queue is full.
+//
+struct Inner<T> {
+    state: Mutex<State<T>>,
+    ready: Condvar,
+    not_full: Condvar,
+    capacity: Option<usize>,
+}
+
+struct State<T> {
+    queue: VecDeque<T>,
+    senders: usize,
+    receiver: bool,
+}
+
+pub struct Sender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+pub struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+}
+
+// The receiver is gone. The value that could not be sent is given back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+// All senders are gone, and the queue is empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+// Also returned by 'channel_simple' (which is never disconnected) and 'channel_mpsc_lockfree' (which is never full).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Disconnected(T),
+}
+
+// Shared with the other channels, just like 'TrySendError'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendTimeoutError<T> {
+    Timeout(T),
+    Disconnected(T),
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "sending on a disconnected channel".fmt(f)
+    }
+}
+
+impl<T: fmt::Debug> Error for SendError<T> {}
+
+impl<T> fmt::Display for TrySendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full(_) => "sending on a full channel".fmt(f),
+            Self::Disconnected(_) => "sending on a disconnected channel".fmt(f),
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for TrySendError<T> {}
+
+impl<T> fmt::Display for SendTimeoutError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout(_) => "timed out waiting on a full channel".fmt(f),
+            Self::Disconnected(_) => "sending on a disconnected channel".fmt(f),
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for SendTimeoutError<T> {}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "receiving on a disconnected channel".fmt(f)
+    }
+}
+
+impl Error for RecvError {}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    with_capacity(None)
+}
+
+// A channel that holds at most 'capacity' values, senders wait until there is room.
+pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(capacity > 0, "capacity must be non-zero");
+
+    with_capacity(Some(capacity))
+}
+
+fn with_capacity<T>(capacity: Option<usize>) -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Inner {
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            senders: 1,
+            receiver: true,
+        }),
+        ready: Condvar::new(),
+        not_full: Condvar::new(),
+        capacity,
+    });
+
+    (
+        Sender {
+            inner: inner.clone(),
+        },
+        Receiver { inner },
+    )
+}
+
+impl<T> Sender<T> {
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.send_with(value, |state| {
+            Some(self.inner.not_full.wait(state).unwrap())
+        })
+        .map_err(|e| match e {
+            SendTimeoutError::Timeout(_) => unreachable!(),
+            SendTimeoutError::Disconnected(value) => SendError(value),
+        })
+    }
+
+    // Never fails with 'Full' on an unbounded channel.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.send_with(value, |_| None).map_err(|e| match e {
+            SendTimeoutError::Timeout(value) => TrySendError::Full(value),
+            SendTimeoutError::Disconnected(value) => TrySendError::Disconnected(value),
+        })
+    }
+
+    // A timeout that does not fit in an 'Instant' waits forever.
+    pub fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        let deadline = Instant::now().checked_add(timeout);
+
+        self.send_with(value, |state| match deadline {
+            None => Some(self.inner.not_full.wait(state).unwrap()),
+            Some(deadline) => {
+                let now = Instant::now();
+                (now < deadline).then(|| {
+                    self.inner
+                        .not_full
+                        .wait_timeout(state, deadline - now)
+                        .unwrap()
+                        .0
+                })
+            }
+        })
+    }
+
+    //
+    // Calls 'wait' for as long as the queue is full. It either waits for room (possibly waking up spuriously or
+    //  because of a timeout, in which case we simply check again), or gives up by returning 'None'.
+    //
+    fn send_with<'a, F>(&'a self, value: T, mut wait: F) -> Result<(), SendTimeoutError<T>>
+    where
+        F: FnMut(MutexGuard<'a, State<T>>) -> Option<MutexGuard<'a, State<T>>>,
+    {
+        let mut state = self.inner.state.lock().unwrap();
+        loop {
+            if !state.receiver {
+                return Err(SendTimeoutError::Disconnected(value));
+            }
+
+            if self
+                .inner
+                .capacity
+                .is_none_or(|capacity| state.queue.len() < capacity)
+            {
+                state.queue.push_back(value);
+                drop(state);
+
+                self.inner.ready.notify_one();
+
+                return Ok(());
+            }
+
+            match wait(state) {
+                Some(s) => state = s,
+                None => return Err(SendTimeoutError::Timeout(value)),
+            }
+        }
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.inner.state.lock().unwrap().senders += 1;
+
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.senders -= 1;
+
+        if state.senders == 0 {
+            drop(state);
+            self.inner.ready.notify_all();
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    // Values that were sent before the last sender was dropped are still received, only then it is an error.
+    pub fn receive(&self) -> Result<T, RecvError> {
+        let mut state = self.inner.state.lock().unwrap();
+        loop {
+            if let Some(value) = state.queue.pop_front() {
+                drop(state);
+
+                if self.inner.capacity.is_some() {
+                    self.inner.not_full.notify_one();
+                }
+
+                return Ok(value);
+            }
+
+            if state.senders == 0 {
+                return Err(RecvError);
+            }
+
+            state = self.inner.ready.wait(state).unwrap();
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.receiver = false;
+
+        // Nobody is going to receive the values that are still queued, so drop them now (outside of the lock).
+        let queue = mem::take(&mut state.queue);
+        drop(state);
+        drop(queue);
+
+        // Wake up the senders that are waiting for room, they are not going to get any.
+        self.inner.not_full.notify_all();
+    }
+}
+
+#[test]
+fn test_channel() {
+    let (s, r) = channel();
+
+    thread::scope(|sc| {
+        for i in 0..3 {
+            let s = s.clone();
+            sc.spawn(move || {
+                for j in 0..10 {
+                    s.send(i * 10 + j).unwrap();
+                }
+            });
+        }
+    });
+
+    drop(s);
+
+    let mut received: Vec<_> = std::iter::from_fn(|| r.receive().ok()).collect();
+    received.sort();
+    assert_eq!(received, (0..30).collect::<Vec<_>>());
+    assert_eq!(r.receive(), Err(RecvError));
+
+    let (s, r) = channel();
+    let t = thread::spawn(move || r.receive());
+    s.send(1).unwrap();
+    assert_eq!(t.join().unwrap(), Ok(1));
+    assert_eq!(s.send(2), Err(SendError(2)));
+
+    // A receiver that is already waiting is woken up when the last sender is dropped.
+    let (s, r) = channel::<i32>();
+    let t = thread::spawn(move || r.receive());
+    thread::sleep(Duration::from_millis(50));
+    drop(s);
+    assert_eq!(t.join().unwrap(), Err(RecvError));
+}
+
+#[test]
+fn test_bounded_channel() {
+    let (s, r) = bounded(2);
+
+    s.try_send(1).unwrap();
+    s.send(2).unwrap();
+    assert_eq!(s.try_send(3), Err(TrySendError::Full(3)));
+    assert_eq!(
+        s.send_timeout(3, Duration::from_millis(10)),
+        Err(SendTimeoutError::Timeout(3))
+    );
+
+    thread::scope(|sc| {
+        let t = sc.spawn(|| s.send(3));
+        thread::sleep(Duration::from_millis(50));
+        assert!(!t.is_finished());
+
+        // Making room unblocks the sender.
+        assert_eq!(r.receive(), Ok(1));
+        assert_eq!(t.join().unwrap(), Ok(()));
+    });
+
+    assert_eq!(r.receive(), Ok(2));
+    assert_eq!(r.receive(), Ok(3));
+
+    s.send(4).unwrap();
+    s.send(5).unwrap();
+
+    // A sender waiting for room finds out when the receiver is gone.
+    let t = thread::spawn(move || s.send_timeout(6, Duration::from_secs(10)));
+    thread::sleep(Duration::from_millis(50));
+    drop(r);
+    assert_eq!(t.join().unwrap(), Err(SendTimeoutError::Disconnected(6)));
+}
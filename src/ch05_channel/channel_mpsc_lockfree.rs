@@ -0,0 +1,318 @@
+use std::cell::{Cell, UnsafeCell};
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+use super::channel_mpsc::{RecvError, SendError, SendTimeoutError, TrySendError};
+use crate::ch04_spin_lock::spin_lock_guard::SpinLock;
+
+//
+// A linked-node MPSC queue (after Dmitry Vyukov). The queue always contains a stub node: the consumer owns the oldest
+//  node (the tail), whose value was already taken. Producers append by swapping their node into the head, and only
+//  then linking it to their predecessor.
+//
+// Pushing is wait-free: a single swap and a store. Popping is lock-free, with one catch: in between the swap and the
+//  link of a producer, the queue is inconsistent. The head moved, but the new node cannot be reached from the tail yet.
+//  The consumer has to wait for that producer to finish.
+//
+struct Node<T> {
+    next: AtomicPtr<Node<T>>,
+    value: MaybeUninit<T>,
+}
+
+impl<T> Node<T> {
+    fn new(value: MaybeUninit<T>) -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            next: AtomicPtr::new(ptr::null_mut()),
+            value,
+        }))
+    }
+}
+
+struct Queue<T> {
+    head: AtomicPtr<Node<T>>,
+    tail: UnsafeCell<*mut Node<T>>,
+}
+
+enum Pop<T> {
+    Data(T),
+    Empty,
+    Inconsistent,
+}
+
+// Values are handed from one thread to another, but never shared.
+unsafe impl<T> Send for Queue<T> where T: Send {}
+unsafe impl<T> Sync for Queue<T> where T: Send {}
+
+impl<T> Queue<T> {
+    fn new() -> Self {
+        let stub = Node::new(MaybeUninit::uninit());
+
+        Self {
+            head: AtomicPtr::new(stub),
+            tail: UnsafeCell::new(stub),
+        }
+    }
+
+    //
+    // The swap on the head is acquire/release: release to publish our node to the next producer (which links to it),
+    //  acquire to see the node of the previous producer. The release store of the link publishes our value to the
+    //  consumer.
+    //
+    fn push(&self, value: T) {
+        let node = Node::new(MaybeUninit::new(value));
+
+        let prev = self.head.swap(node, Ordering::AcqRel);
+        unsafe { (*prev).next.store(node, Ordering::Release) };
+    }
+
+    /// # Safety
+    /// Only one thread at a time may pop.
+    unsafe fn pop(&self) -> Pop<T> {
+        let tail = *self.tail.get();
+        let next = (*tail).next.load(Ordering::Acquire);
+
+        if !next.is_null() {
+            // The next node becomes the stub, its value is moved out.
+            *self.tail.get() = next;
+            drop(Box::from_raw(tail));
+
+            return Pop::Data((*next).value.assume_init_read());
+        }
+
+        if self.head.load(Ordering::Acquire) == tail {
+            Pop::Empty
+        } else {
+            Pop::Inconsistent
+        }
+    }
+}
+
+impl<T> Drop for Queue<T> {
+    fn drop(&mut self) {
+        while let Pop::Data(_) = unsafe { self.pop() } {}
+
+        unsafe { drop(Box::from_raw(*self.tail.get_mut())) };
+    }
+}
+
+//
+// The channel has the same interface as 'channel_mpsc', but sending never takes a lock. The receiver parks when the
+//  queue is empty, and registers its thread for the senders to unpark it.
+//
+// The receiver sets its waiting flag before checking the queue (and the number of senders) one last time, a sender
+//  pushes its value (or drops the last sender) before checking the waiting flag. With a SeqCst fence in between on
+//  both sides, at least one of them sees the store of the other: either the receiver finds the value, or the sender
+//  finds the receiver waiting. Only then, a sender takes the (spin) lock on the registered thread.
+//
+struct Inner<T> {
+    queue: Queue<T>,
+    senders: AtomicUsize,
+    receiver: AtomicBool,
+    waiting: AtomicBool,
+    waiter: SpinLock<Option<Thread>>,
+}
+
+impl<T> Inner<T> {
+    fn wake(&self) {
+        fence(Ordering::SeqCst);
+
+        if self.waiting.load(Ordering::Relaxed) {
+            if let Some(thread) = self.waiter.lock().take() {
+                thread.unpark();
+            }
+        }
+    }
+}
+
+pub struct Sender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+//
+// The receiver can be moved to another thread, but not shared: popping from the queue must be done by one thread at a
+//  time. 'Cell' is Send, but not Sync.
+//
+pub struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Inner {
+        queue: Queue::new(),
+        senders: AtomicUsize::new(1),
+        receiver: AtomicBool::new(true),
+        waiting: AtomicBool::new(false),
+        waiter: SpinLock::new(None),
+    });
+
+    (
+        Sender {
+            inner: inner.clone(),
+        },
+        Receiver {
+            inner,
+            _not_sync: PhantomData,
+        },
+    )
+}
+
+impl<T> Sender<T> {
+    //
+    // A value sent right as the receiver is dropped may be accepted, and is then dropped with the channel instead. Just
+    //  like with any other value that was sent, but not received.
+    //
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        if !self.inner.receiver.load(Ordering::Relaxed) {
+            return Err(SendError(value));
+        }
+
+        self.inner.queue.push(value);
+        self.inner.wake();
+
+        Ok(())
+    }
+
+    //
+    // The queue is unbounded, so sending never waits for room: these are just 'send', for the same interface as an
+    //  unbounded 'channel_mpsc' channel. They never fail with 'Full' or 'Timeout'.
+    //
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.send(value)
+            .map_err(|SendError(value)| TrySendError::Disconnected(value))
+    }
+
+    pub fn send_timeout(&self, value: T, _timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        self.send(value)
+            .map_err(|SendError(value)| SendTimeoutError::Disconnected(value))
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.inner.senders.fetch_add(1, Ordering::Relaxed);
+
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.inner.senders.fetch_sub(1, Ordering::Release) == 1 {
+            self.inner.wake();
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    // Values that were sent before the last sender was dropped are still received, only then it is an error.
+    pub fn receive(&self) -> Result<T, RecvError> {
+        loop {
+            if let Some(result) = self.try_pop() {
+                return result;
+            }
+
+            *self.inner.waiter.lock() = Some(thread::current());
+            self.inner.waiting.store(true, Ordering::Relaxed);
+
+            fence(Ordering::SeqCst);
+
+            match self.try_pop() {
+                Some(result) => {
+                    self.inner.waiting.store(false, Ordering::Relaxed);
+                    return result;
+                }
+                None => thread::park(),
+            }
+
+            self.inner.waiting.store(false, Ordering::Relaxed);
+        }
+    }
+
+    // Returns 'None' if we have to wait. Waiting for an inconsistent queue is done by spinning, it is about to be fixed.
+    fn try_pop(&self) -> Option<Result<T, RecvError>> {
+        loop {
+            //
+            // Load the number of senders before popping: if it was zero, no more values can be pushed, and an empty
+            //  queue really is the end. The acquire load pairs with the release decrement, making their pushes visible.
+            //
+            let senders = self.inner.senders.load(Ordering::Acquire);
+
+            match unsafe { self.inner.queue.pop() } {
+                Pop::Data(value) => return Some(Ok(value)),
+                Pop::Empty if senders == 0 => return Some(Err(RecvError)),
+                Pop::Empty => return None,
+                Pop::Inconsistent => std::hint::spin_loop(),
+            }
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.inner.receiver.store(false, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_channel() {
+    let (s, r) = channel();
+
+    thread::scope(|sc| {
+        for i in 0..3 {
+            let s = s.clone();
+            sc.spawn(move || {
+                for j in 0..1000 {
+                    s.send(i * 1000 + j).unwrap();
+                }
+            });
+        }
+
+        drop(s);
+
+        let mut received = Vec::new();
+        while let Ok(value) = r.receive() {
+            received.push(value);
+        }
+
+        received.sort();
+        assert_eq!(received, (0..3000).collect::<Vec<_>>());
+    });
+
+    // The receiver can be moved to another thread, where it waits for a value.
+    let (s, r) = channel();
+    let t = thread::spawn(move || (r.receive(), r));
+    thread::sleep(Duration::from_millis(50));
+    s.send(Box::new(1)).unwrap();
+
+    let (value, r) = t.join().unwrap();
+    assert_eq!(value, Ok(Box::new(1)));
+
+    s.send(Box::new(2)).unwrap();
+    assert_eq!(s.try_send(Box::new(3)), Ok(()));
+    assert_eq!(s.send_timeout(Box::new(4), Duration::ZERO), Ok(()));
+    drop(r);
+    assert_eq!(s.send(Box::new(5)), Err(SendError(Box::new(5))));
+    assert_eq!(
+        s.try_send(Box::new(6)),
+        Err(TrySendError::Disconnected(Box::new(6)))
+    );
+    assert_eq!(
+        s.send_timeout(Box::new(7), Duration::ZERO),
+        Err(SendTimeoutError::Disconnected(Box::new(7)))
+    );
+
+    // A receiver that is already waiting is woken up when the last sender is dropped.
+    let (s, r) = channel::<i32>();
+    let t = thread::spawn(move || r.receive());
+    thread::sleep(Duration::from_millis(50));
+    drop(s);
+    assert_eq!(t.join().unwrap(), Err(RecvError));
+}
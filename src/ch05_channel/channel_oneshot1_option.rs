@@ -33,6 +33,12 @@ impl<T> Channel<T> {
     }
 }
 
+impl<T> Default for Channel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[test]
 fn test_channel() {
     let c = Channel::<i32>::new();
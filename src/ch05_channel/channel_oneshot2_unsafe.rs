@@ -1,4 +1,4 @@
-use std::{
+use core::{
     cell::UnsafeCell,
     mem::MaybeUninit,
     sync::atomic::{AtomicBool, Ordering},
@@ -55,6 +55,12 @@ impl<T> Channel<T> {
     }
 }
 
+impl<T> Default for Channel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[test]
 fn test_channel() {
     let c = Channel::<i32>::new();
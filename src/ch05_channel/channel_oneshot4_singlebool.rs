@@ -1,4 +1,4 @@
-use std::{
+use core::{
     cell::UnsafeCell,
     mem::MaybeUninit,
     sync::atomic::{AtomicU8, Ordering},
@@ -80,6 +80,12 @@ impl<T> Channel<T> {
     }
 }
 
+impl<T> Default for Channel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Drop for Channel<T> {
     fn drop(&mut self) {
         if *self.state.get_mut() == READY {
@@ -1,10 +1,8 @@
-use std::{
+use alloc::sync::Arc;
+use core::{
     cell::UnsafeCell,
     mem::MaybeUninit,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
+    sync::atomic::{AtomicBool, Ordering},
 };
 
 #[cfg(test)]
@@ -1,5 +1,10 @@
 use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
 use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use super::channel_mpsc::{SendTimeoutError, TrySendError};
 
 #[cfg(test)]
 use std::thread;
@@ -7,31 +12,162 @@ use std::thread;
 pub struct Channel<T> {
     queue: Mutex<VecDeque<T>>,
     ready: Condvar,
+    not_full: Condvar,
+    capacity: Option<usize>,
+}
+
+// Nothing was sent before the timeout (or deadline).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvTimeoutError;
+
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "timed out waiting on an empty channel".fmt(f)
+    }
 }
 
+impl Error for RecvTimeoutError {}
+
 impl<T> Channel<T> {
     pub const fn new() -> Self {
+        Self::with_capacity(None)
+    }
+
+    //
+    // A bounded channel holds at most 'capacity' values: sending blocks while it is full, until a receiver makes room.
+    //  Senders wait on a second condvar, so that a receiver only wakes up senders (and a sender only receivers).
+    //
+    // The send errors are shared with 'channel_mpsc'. Without handles, this channel cannot be disconnected: only 'Full'
+    //  and 'Timeout' are ever returned.
+    //
+    pub const fn bounded(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be non-zero");
+
+        Self::with_capacity(Some(capacity))
+    }
+
+    const fn with_capacity(capacity: Option<usize>) -> Self {
         Self {
             queue: Mutex::new(VecDeque::new()),
             ready: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity,
         }
     }
 
     pub fn send(&self, value: T) {
-        self.queue.lock().unwrap().push_back(value);
+        let mut g = self.queue.lock().unwrap();
+        while self.is_full(&g) {
+            g = self.not_full.wait(g).unwrap();
+        }
+
+        g.push_back(value);
+        drop(g);
+
+        self.ready.notify_one();
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let mut g = self.queue.lock().unwrap();
+        if self.is_full(&g) {
+            return Err(TrySendError::Full(value));
+        }
+
+        g.push_back(value);
+        drop(g);
+
+        self.ready.notify_one();
+        Ok(())
+    }
+
+    // A timeout that does not fit in an 'Instant' waits forever.
+    pub fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.send_deadline(value, deadline),
+            None => {
+                self.send(value);
+                Ok(())
+            }
+        }
+    }
+
+    // Just like for receiving, a wakeup is no guarantee for room in the channel, nor for the deadline having passed.
+    pub fn send_deadline(&self, value: T, deadline: Instant) -> Result<(), SendTimeoutError<T>> {
+        let mut g = self.queue.lock().unwrap();
+        while self.is_full(&g) {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(SendTimeoutError::Timeout(value));
+            }
+
+            g = self.not_full.wait_timeout(g, deadline - now).unwrap().0;
+        }
+
+        g.push_back(value);
+        drop(g);
+
         self.ready.notify_one();
+        Ok(())
     }
 
     pub fn receive(&self) -> T {
         let mut g = self.queue.lock().unwrap();
         loop {
-            if let Some(value) = g.pop_front() {
+            if let Some(value) = self.pop(&mut g) {
                 return value;
             }
 
             g = self.ready.wait(g).unwrap();
         }
     }
+
+    pub fn try_receive(&self) -> Option<T> {
+        self.pop(&mut self.queue.lock().unwrap())
+    }
+
+    // A timeout that does not fit in an 'Instant' waits forever.
+    pub fn receive_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.receive_deadline(deadline),
+            None => Ok(self.receive()),
+        }
+    }
+
+    //
+    // Waking up from 'wait_timeout' does not mean there is a value (spurious wakeups, or another receiver got to it
+    //  first), nor that the deadline passed (the timeout result only covers this single wait). So we check both again
+    //  every time, waiting for whatever time is left.
+    //
+    pub fn receive_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        let mut g = self.queue.lock().unwrap();
+        loop {
+            if let Some(value) = self.pop(&mut g) {
+                return Ok(value);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError);
+            }
+
+            g = self.ready.wait_timeout(g, deadline - now).unwrap().0;
+        }
+    }
+
+    fn is_full(&self, queue: &VecDeque<T>) -> bool {
+        self.capacity
+            .is_some_and(|capacity| queue.len() >= capacity)
+    }
+
+    // Taking a value out of a bounded channel makes room for a waiting sender.
+    fn pop(&self, queue: &mut VecDeque<T>) -> Option<T> {
+        let value = queue.pop_front()?;
+        if self.capacity.is_some() {
+            self.not_full.notify_one();
+        }
+
+        Some(value)
+    }
 }
 
 impl<T> Default for Channel<T> {
@@ -54,3 +190,61 @@ fn test_channel() {
         });
     });
 }
+
+#[test]
+fn test_channel_timeout() {
+    let c = Channel::<i32>::new();
+
+    assert_eq!(c.try_receive(), None);
+    assert_eq!(
+        c.receive_timeout(Duration::from_millis(10)),
+        Err(RecvTimeoutError)
+    );
+    assert_eq!(c.receive_deadline(Instant::now()), Err(RecvTimeoutError));
+
+    c.send(1);
+    assert_eq!(c.try_receive(), Some(1));
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(50));
+            c.send(2);
+        });
+
+        assert_eq!(c.receive_timeout(Duration::from_secs(10)), Ok(2));
+    });
+}
+
+#[test]
+fn test_bounded_channel() {
+    let c = Channel::bounded(2);
+
+    c.send(1);
+    assert_eq!(c.try_send(2), Ok(()));
+    assert_eq!(c.try_send(3), Err(TrySendError::Full(3)));
+    assert_eq!(
+        c.send_timeout(3, Duration::from_millis(10)),
+        Err(SendTimeoutError::Timeout(3))
+    );
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(50));
+            assert_eq!(c.receive(), 1);
+        });
+
+        // Blocks until the receiver above makes room.
+        c.send(3);
+    });
+
+    assert_eq!(
+        c.send_timeout(4, Duration::ZERO),
+        Err(SendTimeoutError::Timeout(4))
+    );
+    assert_eq!(c.try_receive(), Some(2));
+    assert_eq!(c.send_timeout(4, Duration::from_secs(10)), Ok(()));
+
+    assert_eq!(c.receive(), 3);
+    assert_eq!(c.receive(), 4);
+    assert_eq!(c.try_receive(), None);
+}
@@ -0,0 +1,288 @@
+use alloc::{boxed::Box, sync::Arc};
+use core::{
+    cell::UnsafeCell,
+    cmp,
+    mem::MaybeUninit,
+    ops::Deref,
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[cfg(test)]
+use std::thread;
+
+// Aligned to a (typical) cache line, so that the producer and the consumer do not interfere with each other.
+#[repr(align(64))]
+struct CachePadded<T>(T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+//
+// The indices are never wrapped to the capacity, only when indexing into the buffer (which is cheap, as the capacity
+//  is a power of two). This way 'tail - head' is the number of values in the buffer, and a full buffer can be told
+//  apart from an empty one without wasting a slot. They do wrap around at 'usize::MAX', which works out the same, as
+//  the capacity divides 2^usize::BITS.
+//
+struct Inner<T> {
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
+}
+
+// Values are handed from the producer to the consumer, but never shared.
+unsafe impl<T> Send for Inner<T> where T: Send {}
+unsafe impl<T> Sync for Inner<T> where T: Send {}
+
+impl<T> Inner<T> {
+    fn slot(&self, index: usize) -> *mut T {
+        let i = index & (self.buffer.len() - 1);
+        UnsafeCell::raw_get(unsafe { self.buffer.as_ptr().add(i) }).cast()
+    }
+
+    //
+    // Copies 'len' values to or from the buffer, starting at 'index'. The copy is split in two where it wraps around
+    //  the end of the buffer: 'copy' gets the first slot, the offset into the batch and the length of each part.
+    //
+    fn copy<F>(&self, index: usize, len: usize, mut copy: F)
+    where
+        F: FnMut(*mut T, usize, usize),
+    {
+        let i = index & (self.buffer.len() - 1);
+        let first = cmp::min(len, self.buffer.len() - i);
+
+        copy(self.slot(index), 0, first);
+        copy(self.slot(0), first, len - first);
+    }
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let (head, tail) = (*self.head.0.get_mut(), *self.tail.0.get_mut());
+
+        let mut index = head;
+        while index != tail {
+            unsafe { ptr::drop_in_place(self.slot(index)) };
+            index = index.wrapping_add(1);
+        }
+    }
+}
+
+///
+/// Pros:
+///   - Wait-free: pushing and popping never wait for the other side, they fail if the buffer is full or empty.
+///   - The producer only writes the tail, the consumer only writes the head, each on its own cache line.
+///   - Both sides keep a cached copy of the index of the other side, and only reload it when the buffer looks full
+///     (or empty). Until then, there is no cache line traffic between the two at all, apart from the values themselves.
+///
+/// Cons:
+///   - Bounded, and there is no blocking: on a full (or empty) buffer, the caller has to decide what to do.
+///   - There is exactly one producer and one consumer: the handles can be moved to another thread, but not cloned.
+///
+pub struct Producer<T> {
+    inner: Arc<Inner<T>>,
+    tail: usize,
+    cached_head: usize,
+}
+
+pub struct Consumer<T> {
+    inner: Arc<Inner<T>>,
+    head: usize,
+    cached_tail: usize,
+}
+
+// The capacity is rounded up to a power of two.
+pub fn channel<T>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    assert!(capacity > 0, "capacity must be non-zero");
+
+    let capacity = capacity
+        .checked_next_power_of_two()
+        .expect("capacity overflow");
+    let inner = Arc::new(Inner {
+        head: CachePadded(AtomicUsize::new(0)),
+        tail: CachePadded(AtomicUsize::new(0)),
+        buffer: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+    });
+
+    (
+        Producer {
+            inner: inner.clone(),
+            tail: 0,
+            cached_head: 0,
+        },
+        Consumer {
+            inner,
+            head: 0,
+            cached_tail: 0,
+        },
+    )
+}
+
+impl<T> Producer<T> {
+    pub fn capacity(&self) -> usize {
+        self.inner.buffer.len()
+    }
+
+    // Gives the value back if the buffer is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        if self.free(1) == 0 {
+            return Err(value);
+        }
+
+        unsafe { self.inner.slot(self.tail).write(value) };
+        self.publish(1);
+
+        Ok(())
+    }
+
+    // Pushes as many values as there is room for, and returns how many that were.
+    pub fn push_slice(&mut self, values: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        let n = cmp::min(values.len(), self.free(values.len()));
+
+        self.inner.copy(self.tail, n, |slot, offset, len| unsafe {
+            ptr::copy_nonoverlapping(values.as_ptr().add(offset), slot, len)
+        });
+        self.publish(n);
+
+        n
+    }
+
+    //
+    // The number of free slots. Only if the cached head of the consumer says there are not as many as we want, we load
+    //  the actual head. The acquire load pairs with the release store of the consumer, so it is done reading the slots
+    //  it gave back before we overwrite them.
+    //
+    fn free(&mut self, wanted: usize) -> usize {
+        let capacity = self.capacity();
+
+        let free = capacity - self.tail.wrapping_sub(self.cached_head);
+        if free >= wanted {
+            return free;
+        }
+
+        self.cached_head = self.inner.head.load(Ordering::Acquire);
+
+        capacity - self.tail.wrapping_sub(self.cached_head)
+    }
+
+    // The release store makes the values visible to the consumer.
+    fn publish(&mut self, n: usize) {
+        self.tail = self.tail.wrapping_add(n);
+        self.inner.tail.store(self.tail, Ordering::Release);
+    }
+}
+
+impl<T> Consumer<T> {
+    pub fn capacity(&self) -> usize {
+        self.inner.buffer.len()
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.available(1) == 0 {
+            return None;
+        }
+
+        let value = unsafe { self.inner.slot(self.head).read() };
+        self.release(1);
+
+        Some(value)
+    }
+
+    // Pops as many values as are available and fit in 'values', and returns how many that were.
+    pub fn pop_into(&mut self, values: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let n = cmp::min(values.len(), self.available(values.len()));
+
+        self.inner.copy(self.head, n, |slot, offset, len| unsafe {
+            ptr::copy_nonoverlapping(slot, values.as_mut_ptr().add(offset), len)
+        });
+        self.release(n);
+
+        n
+    }
+
+    //
+    // The number of values in the buffer. Only if the cached tail of the producer says there are not as many as we want,
+    //  we load the actual tail. The acquire load pairs with the release store of the producer, making the values
+    //  visible to us.
+    //
+    fn available(&mut self, wanted: usize) -> usize {
+        let available = self.cached_tail.wrapping_sub(self.head);
+        if available >= wanted {
+            return available;
+        }
+
+        self.cached_tail = self.inner.tail.load(Ordering::Acquire);
+
+        self.cached_tail.wrapping_sub(self.head)
+    }
+
+    // The release store gives the slots back to the producer, only after we are done reading them.
+    fn release(&mut self, n: usize) {
+        self.head = self.head.wrapping_add(n);
+        self.inner.head.store(self.head, Ordering::Release);
+    }
+}
+
+#[test]
+fn test_channel() {
+    const N: usize = 10_000;
+
+    let (mut p, mut c) = channel(100);
+    assert_eq!(p.capacity(), 128);
+
+    thread::scope(|s| {
+        s.spawn(move || {
+            for i in 0..N {
+                let mut value = Box::new(i);
+                while let Err(v) = p.push(value) {
+                    value = v;
+                    thread::yield_now();
+                }
+            }
+        });
+
+        for i in 0..N {
+            loop {
+                if let Some(value) = c.pop() {
+                    assert_eq!(*value, i);
+                    break;
+                }
+
+                thread::yield_now();
+            }
+        }
+    });
+
+    // Batches, wrapping around the end of the buffer.
+    let (mut p, mut c) = channel(4);
+    let mut out = [0; 8];
+
+    assert_eq!(p.push_slice(&[1, 2, 3]), 3);
+    assert_eq!(c.pop_into(&mut out[..2]), 2);
+    assert_eq!(out[..2], [1, 2]);
+
+    assert_eq!(p.push_slice(&[4, 5, 6, 7, 8]), 3);
+    assert_eq!(p.push(8), Err(8));
+    assert_eq!(c.pop_into(&mut out), 4);
+    assert_eq!(out[..4], [3, 4, 5, 6]);
+    assert_eq!(c.pop(), None);
+
+    // Values that were not popped are dropped with the channel.
+    let (mut p, c) = channel(2);
+    p.push(Box::new(1)).unwrap();
+    drop(p);
+    drop(c);
+}
@@ -1,8 +1,16 @@
+#[cfg(feature = "std")]
+pub mod channel_mpsc;
+#[cfg(feature = "std")]
+pub mod channel_mpsc_lockfree;
+#[cfg(feature = "std")]
 pub mod channel_oneshot1_option;
 pub mod channel_oneshot2_unsafe;
 pub mod channel_oneshot3_checked;
 pub mod channel_oneshot4_singlebool;
 pub mod channel_oneshot5_safetypes;
 pub mod channel_oneshot6_borrowing;
+#[cfg(feature = "std")]
 pub mod channel_oneshot7_blocking;
+#[cfg(feature = "std")]
 pub mod channel_simple;
+pub mod channel_spsc;
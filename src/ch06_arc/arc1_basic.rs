@@ -1,9 +1,13 @@
-use std::{
+use core::{
     ops::Deref,
     ptr::NonNull,
     sync::atomic::{fence, AtomicUsize, Ordering},
 };
 
+use alloc::boxed::Box;
+
+use super::abort;
+
 #[cfg(test)]
 use std::thread;
 
@@ -27,7 +31,7 @@ impl<T> Arc<T> {
     }
 
     fn data(&self) -> &ArcData<T> {
-        unsafe { &self.state.as_ref() }
+        unsafe { self.state.as_ref() }
     }
 
     //
@@ -48,7 +52,7 @@ impl<T> Arc<T> {
 impl<T> Clone for Arc<T> {
     fn clone(&self) -> Self {
         if self.data().ref_count.fetch_add(1, Ordering::Relaxed) > usize::MAX / 2 {
-            std::process::abort();
+            abort();
         }
 
         Arc { state: self.state }
@@ -1,10 +1,14 @@
-use std::{
+use core::{
     cell::UnsafeCell,
     ops::Deref,
     ptr::NonNull,
     sync::atomic::{fence, AtomicUsize, Ordering},
 };
 
+use alloc::boxed::Box;
+
+use super::abort;
+
 #[cfg(test)]
 use std::thread;
 
@@ -61,7 +65,7 @@ impl<T> Clone for Arc<T> {
     fn clone(&self) -> Self {
         let weak = self.weak.clone();
         if weak.data().arc_ref_count.fetch_add(1, Ordering::Relaxed) > usize::MAX / 2 {
-            std::process::abort();
+            abort();
         }
 
         Arc { weak }
@@ -95,7 +99,7 @@ impl<T> Deref for Arc<T> {
 
 impl<T> Weak<T> {
     fn data(&self) -> &ArcData<T> {
-        unsafe { &self.state.as_ref() }
+        unsafe { self.state.as_ref() }
     }
 
     pub fn upgrade(&self) -> Option<Arc<T>> {
@@ -125,7 +129,7 @@ impl<T> Weak<T> {
 impl<T> Clone for Weak<T> {
     fn clone(&self) -> Self {
         if self.data().weak_ref_count.fetch_add(1, Ordering::Relaxed) > usize::MAX / 2 {
-            std::process::abort();
+            abort();
         }
 
         Weak { state: self.state }
@@ -1,4 +1,4 @@
-use std::{
+use core::{
     cell::UnsafeCell,
     mem::ManuallyDrop,
     ops::Deref,
@@ -6,6 +6,10 @@ use std::{
     sync::atomic::{fence, AtomicUsize, Ordering},
 };
 
+use alloc::boxed::Box;
+
+use super::abort;
+
 #[cfg(test)]
 use std::thread;
 
@@ -64,7 +68,7 @@ impl<T> Arc<T> {
         let mut ref_count = arc.data().weak_ref_count.load(Ordering::Relaxed);
         loop {
             if ref_count == usize::MAX {
-                std::hint::spin_loop();
+                core::hint::spin_loop();
                 ref_count = arc.data().weak_ref_count.load(Ordering::Relaxed);
                 continue;
             }
@@ -89,7 +93,7 @@ impl<T> Arc<T> {
 impl<T> Clone for Arc<T> {
     fn clone(&self) -> Self {
         if self.data().arc_ref_count.fetch_add(1, Ordering::Relaxed) > usize::MAX / 2 {
-            std::process::abort();
+            abort();
         }
 
         Arc { state: self.state }
@@ -152,7 +156,7 @@ impl<T> Weak<T> {
 impl<T> Clone for Weak<T> {
     fn clone(&self) -> Self {
         if self.data().weak_ref_count.fetch_add(1, Ordering::Relaxed) > usize::MAX / 2 {
-            std::process::abort();
+            abort();
         }
 
         Weak { state: self.state }
@@ -1,3 +1,27 @@
 pub mod arc1_basic;
 pub mod arc2_weak;
 pub mod arc3_optimized;
+
+//
+// Overflowing a reference counter is not something a program can recover from, and panicking would not stop the other
+//  clones from being used. Without 'std' there is no process to abort, but panicking while already panicking (from a
+//  destructor during unwinding) does abort.
+//
+#[cfg(feature = "std")]
+fn abort() -> ! {
+    std::process::abort()
+}
+
+#[cfg(not(feature = "std"))]
+fn abort() -> ! {
+    struct Abort;
+
+    impl Drop for Abort {
+        fn drop(&mut self) {
+            panic!("aborting");
+        }
+    }
+
+    let _abort = Abort;
+    panic!("reference count overflow");
+}
@@ -0,0 +1,214 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(any(feature = "std", test))]
+use std::thread;
+#[cfg(feature = "std")]
+use std::thread::Thread;
+
+#[cfg(feature = "std")]
+use crate::ch04_spin_lock::spin_lock_guard::SpinLock;
+
+#[cfg(all(test, feature = "std"))]
+use std::time::Duration;
+
+//
+// The state of a barrier is a single atomic word: the number of threads that arrived in the current generation in the
+//  lower half, the generation counter in the upper half.
+//
+// The last thread to arrive (the leader) resets the count and bumps the generation in a single step, so there is no
+//  window in which an early thread of the next generation could be counted in the current one. Waiting threads wait
+//  for the generation to change. It may wrap around, but a generation cannot pass without all of its threads.
+//
+const COUNT_BITS: u32 = usize::BITS / 2;
+const COUNT_MASK: usize = (1 << COUNT_BITS) - 1;
+const GENERATION: usize = 1 << COUNT_BITS;
+
+struct State {
+    n: usize,
+    state: AtomicUsize,
+}
+
+impl State {
+    const fn new(n: usize) -> Self {
+        assert!(n > 0 && n <= COUNT_MASK, "invalid number of threads");
+
+        Self {
+            n,
+            state: AtomicUsize::new(0),
+        }
+    }
+
+    //
+    // Returns the generation we have to wait for to pass, or 'None' for the leader.
+    //
+    // Arriving is acquire/release: the leader acquires everything the other threads did before arriving (all arrivals
+    //  are in the release sequence of the first). Bumping the generation releases all of that to the waiting threads.
+    //
+    fn arrive(&self) -> Option<usize> {
+        let s = self.state.fetch_add(1, Ordering::AcqRel);
+        if s & COUNT_MASK == self.n - 1 {
+            self.state.fetch_add(GENERATION - self.n, Ordering::Release);
+            None
+        } else {
+            Some(s & !COUNT_MASK)
+        }
+    }
+
+    fn passed(&self, generation: usize) -> bool {
+        self.state.load(Ordering::Acquire) & !COUNT_MASK != generation
+    }
+}
+
+///
+/// Pros:
+///   - No system calls at all, the threads are released as soon as the last one arrives.
+///   - Reusable: the same barrier can be waited on for any number of generations (e.g. phases of an algorithm).
+///
+/// Cons:
+///   - Waiting threads burn CPU time, which is only a good idea if all threads are expected to arrive at about the same
+///     time (and there are at least as many cores as threads).
+///
+pub struct SpinBarrier {
+    state: State,
+}
+
+impl SpinBarrier {
+    pub const fn new(n: usize) -> Self {
+        Self {
+            state: State::new(n),
+        }
+    }
+
+    // Returns true for exactly one thread per generation: the last one to arrive.
+    pub fn wait(&self) -> bool {
+        match self.state.arrive() {
+            None => true,
+            Some(generation) => {
+                while !self.state.passed(generation) {
+                    core::hint::spin_loop();
+                }
+
+                false
+            }
+        }
+    }
+}
+
+///
+/// Pros:
+///   - Waiting threads are parked, so they do not take CPU time away from the threads that still have to arrive.
+///   - Reusable: the same barrier can be waited on for any number of generations (e.g. phases of an algorithm).
+///
+/// Cons:
+///   - Releasing the waiting threads takes a system call per thread (to unpark it).
+///
+/// Notes:
+///   - A waiting thread registers itself only if the generation did not pass yet, checked while holding the waiter
+///     lock. The leader bumps the generation before it takes the waiter lock to unpark everyone: a thread that gets the
+///     lock after the leader sees the new generation, a thread that gets it before is found by the leader.
+///   - Thread parking may wake up spuriously, so parking is done in a loop.
+///
+#[cfg(feature = "std")]
+pub struct Barrier {
+    state: State,
+    waiters: SpinLock<Vec<Thread>>,
+}
+
+#[cfg(feature = "std")]
+impl Barrier {
+    pub const fn new(n: usize) -> Self {
+        Self {
+            state: State::new(n),
+            waiters: SpinLock::new(Vec::new()),
+        }
+    }
+
+    // Returns true for exactly one thread per generation: the last one to arrive.
+    pub fn wait(&self) -> bool {
+        match self.state.arrive() {
+            None => {
+                for thread in self.waiters.lock().drain(..) {
+                    thread.unpark();
+                }
+
+                true
+            }
+            Some(generation) => {
+                {
+                    let mut waiters = self.waiters.lock();
+                    if self.state.passed(generation) {
+                        return false;
+                    }
+
+                    waiters.push(thread::current());
+                }
+
+                while !self.state.passed(generation) {
+                    thread::park();
+                }
+
+                false
+            }
+        }
+    }
+}
+
+#[test]
+fn test_spin_barrier() {
+    const THREADS: usize = 3;
+
+    let b = SpinBarrier::new(THREADS);
+    let arrived = AtomicUsize::new(0);
+    let leaders = AtomicUsize::new(0);
+
+    thread::scope(|s| {
+        for _ in 0..THREADS {
+            s.spawn(|| {
+                for phase in 1..=10 {
+                    arrived.fetch_add(1, Ordering::Relaxed);
+                    if b.wait() {
+                        leaders.fetch_add(1, Ordering::Relaxed);
+                    }
+
+                    // Everything done before the barrier is visible after it.
+                    assert!(arrived.load(Ordering::Relaxed) >= phase * THREADS);
+                }
+            });
+        }
+    });
+
+    assert_eq!(leaders.into_inner(), 10);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_barrier() {
+    const THREADS: usize = 3;
+
+    let b = Barrier::new(THREADS);
+    let arrived = AtomicUsize::new(0);
+    let leaders = AtomicUsize::new(0);
+
+    thread::scope(|s| {
+        for i in 0..THREADS {
+            let (b, arrived, leaders) = (&b, &arrived, &leaders);
+            s.spawn(move || {
+                for phase in 1..=10 {
+                    // Make different threads arrive last, and make sure the others actually park.
+                    if phase % THREADS == i {
+                        thread::sleep(Duration::from_millis(5));
+                    }
+
+                    arrived.fetch_add(1, Ordering::Relaxed);
+                    if b.wait() {
+                        leaders.fetch_add(1, Ordering::Relaxed);
+                    }
+
+                    assert!(arrived.load(Ordering::Relaxed) >= phase * THREADS);
+                }
+            });
+        }
+    });
+
+    assert_eq!(leaders.into_inner(), 10);
+    assert!(b.waiters.lock().is_empty());
+}
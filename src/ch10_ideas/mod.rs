@@ -0,0 +1,4 @@
+pub mod barrier;
+#[cfg(feature = "std")]
+pub mod once;
+pub mod seqlock;
@@ -0,0 +1,311 @@
+use std::cell::{Cell, UnsafeCell};
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::thread::{self, Thread};
+
+use crate::ch04_spin_lock::spin_lock_guard::SpinLock;
+
+#[cfg(test)]
+use std::{sync::atomic::AtomicUsize, time::Duration};
+
+const INCOMPLETE: u8 = 0;
+const RUNNING: u8 = 1;
+const COMPLETE: u8 = 2;
+const POISONED: u8 = 3;
+
+///
+/// Pros:
+///   - Once completed, checking for completion is a single acquire load.
+///   - Threads that find the initialization running are parked, instead of spinning for as long as it takes.
+///
+/// Cons:
+///   - A panic during initialization poisons the 'Once' for good: all current and future callers panic as well.
+///
+/// Notes:
+///   - A waiting thread registers itself only if the state is still RUNNING, checked while holding the waiter lock. The
+///     initializing thread stores the final state before it takes the waiter lock to unpark everyone: a thread that
+///     gets the lock after it sees the final state, a thread that gets it before is found by the initializing thread.
+///
+pub struct Once {
+    state: AtomicU8,
+    waiters: SpinLock<Vec<Thread>>,
+}
+
+//
+// Stores the final state and wakes up the waiting threads when dropped. Unless told otherwise (i.e. the initialization
+//  function returned), that final state is POISONED.
+//
+struct Completion<'a> {
+    once: &'a Once,
+    state: u8,
+}
+
+impl Drop for Completion<'_> {
+    fn drop(&mut self) {
+        //
+        // State: RUNNING --> COMPLETE/POISONED
+        //
+        // Release, to make everything the initialization function did visible to the threads that see COMPLETE.
+        //
+        self.once.state.store(self.state, Ordering::Release);
+
+        for thread in self.once.waiters.lock().drain(..) {
+            thread.unpark();
+        }
+    }
+}
+
+impl Once {
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(INCOMPLETE),
+            waiters: SpinLock::new(Vec::new()),
+        }
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Ordering::Acquire) == COMPLETE
+    }
+
+    // Runs 'f' if no call to 'call_once' ran (or is running) it before. Once this returns, initialization is complete.
+    pub fn call_once<F>(&self, f: F)
+    where
+        F: FnOnce(),
+    {
+        if self.is_completed() {
+            return;
+        }
+
+        let mut f = Some(f);
+
+        loop {
+            //
+            // State: INCOMPLETE --> RUNNING
+            //
+            // Acquire on failure as well: seeing COMPLETE means we are about to use whatever was initialized.
+            //
+            match self.state.compare_exchange(
+                INCOMPLETE,
+                RUNNING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    let mut completion = Completion {
+                        once: self,
+                        state: POISONED,
+                    };
+
+                    (f.take().unwrap())();
+                    completion.state = COMPLETE;
+
+                    return;
+                }
+                Err(COMPLETE) => return,
+                Err(POISONED) => panic!("Once instance has previously been poisoned"),
+                Err(_) => self.wait(),
+            }
+        }
+    }
+
+    fn wait(&self) {
+        {
+            let mut waiters = self.waiters.lock();
+            if self.state.load(Ordering::Relaxed) != RUNNING {
+                return;
+            }
+
+            waiters.push(thread::current());
+        }
+
+        // Thread parking may wake up spuriously.
+        while self.state.load(Ordering::Relaxed) == RUNNING {
+            thread::park();
+        }
+    }
+}
+
+impl Default for Once {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+///
+/// Pros:
+///   - A value that is written once (by whichever thread gets there first) and can be shared by reference afterwards.
+///
+/// Cons:
+///   - Same poisoning behavior as 'Once': a panicking initialization function makes all accesses panic.
+///
+pub struct OnceCell<T> {
+    once: Once,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// The value may be set by one thread and dropped by another (Send), and is shared by reference (Sync).
+unsafe impl<T> Sync for OnceCell<T> where T: Send + Sync {}
+
+impl<T> OnceCell<T> {
+    pub const fn new() -> Self {
+        Self {
+            once: Once::new(),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        if self.once.is_completed() {
+            Some(unsafe { (*self.value.get()).assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_or_init<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        self.once.call_once(|| unsafe {
+            (*self.value.get()).write(f());
+        });
+
+        unsafe { (*self.value.get()).assume_init_ref() }
+    }
+
+    // Gives the value back if the cell was already initialized.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        let mut value = Some(value);
+
+        self.once.call_once(|| unsafe {
+            (*self.value.get()).write(value.take().unwrap());
+        });
+
+        match value {
+            None => Ok(()),
+            Some(value) => Err(value),
+        }
+    }
+
+    pub fn into_inner(mut self) -> Option<T> {
+        if *self.once.state.get_mut() == COMPLETE {
+            *self.once.state.get_mut() = INCOMPLETE;
+            Some(unsafe { self.value.get_mut().assume_init_read() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Default for OnceCell<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for OnceCell<T> {
+    fn drop(&mut self) {
+        if *self.once.state.get_mut() == COMPLETE {
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+///
+/// Pros:
+///   - Initialized on first access, e.g. for a static that cannot be created in a const context.
+///
+/// Cons:
+///   - Same poisoning behavior as 'Once': a panicking initialization function makes all accesses panic.
+///
+pub struct Lazy<T, F = fn() -> T> {
+    cell: OnceCell<T>,
+    init: Cell<Option<F>>,
+}
+
+// The initialization function is only ever taken by the one thread that runs it, which may be any thread (Send).
+unsafe impl<T, F> Sync for Lazy<T, F>
+where
+    T: Send + Sync,
+    F: Send,
+{
+}
+
+impl<T, F> Lazy<T, F>
+where
+    F: FnOnce() -> T,
+{
+    pub const fn new(init: F) -> Self {
+        Self {
+            cell: OnceCell::new(),
+            init: Cell::new(Some(init)),
+        }
+    }
+
+    // An associated function instead of a method, so it does not shadow a method of 'T' with the same name.
+    pub fn force(this: &Self) -> &T {
+        this.cell.get_or_init(|| match this.init.take() {
+            Some(init) => init(),
+            None => unreachable!("initialization function already taken"),
+        })
+    }
+}
+
+impl<T, F> Deref for Lazy<T, F>
+where
+    F: FnOnce() -> T,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        Lazy::force(self)
+    }
+}
+
+#[test]
+fn test_once() {
+    static LAZY: Lazy<Vec<i32>> = Lazy::new(|| vec![1, 2, 3]);
+
+    let once = Once::new();
+    let cell = OnceCell::new();
+    let calls = AtomicUsize::new(0);
+
+    thread::scope(|s| {
+        for i in 0..3 {
+            let (once, cell, calls) = (&once, &cell, &calls);
+            s.spawn(move || {
+                //
+                // The first thread keeps the initialization running for a while, so that the others have to park.
+                //
+                once.call_once(|| {
+                    calls.fetch_add(1, Ordering::Relaxed);
+                    thread::sleep(Duration::from_millis(50));
+                });
+                assert!(once.is_completed());
+
+                let v = cell.get_or_init(|| {
+                    thread::sleep(Duration::from_millis(50));
+                    i
+                });
+                assert_eq!(cell.get(), Some(v));
+
+                assert_eq!(LAZY.len(), 3);
+            });
+        }
+    });
+
+    assert_eq!(calls.into_inner(), 1);
+    assert!(once.waiters.lock().is_empty());
+    assert!(cell.set(42).is_err());
+    assert!(cell.into_inner().is_some());
+    assert_eq!(*LAZY, [1, 2, 3]);
+
+    // A panic during initialization poisons.
+    let lazy = Lazy::new(|| -> i32 { panic!("oops") });
+    thread::scope(|s| {
+        assert!(s.spawn(|| *lazy).join().is_err());
+        assert!(s.spawn(|| *lazy).join().is_err());
+    });
+    assert_eq!(lazy.cell.once.state.load(Ordering::Relaxed), POISONED);
+}
@@ -0,0 +1,236 @@
+use core::cell::UnsafeCell;
+use core::mem::{self, MaybeUninit};
+use core::sync::atomic::{fence, AtomicU8, AtomicUsize, Ordering};
+
+use crate::ch04_spin_lock::spin_lock_guard::SpinLock;
+
+#[cfg(test)]
+use std::thread;
+
+///
+/// Pros:
+///   - Readers never write to shared memory, so they do not contend with each other (or slow down the writer) at all.
+///   - Writers never wait for readers.
+///
+/// Cons:
+///   - Readers retry while a write is in progress, so they can starve under a constant stream of writes.
+///   - Only for 'Copy' data without padding ('NoPadding'): a reader may copy a half-written value, which is only
+///     thrown away after the fact.
+///
+/// Notes:
+///   - The sequence number is odd while a write is in progress. A reader copies the value in between two loads of the
+///     sequence number, and only keeps the copy if both loads gave the same even number.
+///   - Readers and writers access the value concurrently, so all of them copy it with (relaxed) atomic operations, byte
+///     by byte. Copying padding bytes that way would read uninitialized memory, hence the 'NoPadding' bound.
+///
+pub struct SeqLock<T> {
+    seq: AtomicUsize,
+    writer: SpinLock<()>,
+    value: UnsafeCell<T>,
+}
+
+// Readers only ever get a copy of the value, so 'T' only has to be Send.
+unsafe impl<T> Sync for SeqLock<T> where T: NoPadding + Send {}
+
+/// Marker for types that can be copied byte by byte, i.e. all of their bytes are always initialized.
+///
+/// # Safety
+/// Implementors must not have any padding bytes (nor contain any types that do), e.g. a '#[repr(C)]' struct whose
+/// fields are laid out without gaps and fill up its size.
+pub unsafe trait NoPadding: Copy {}
+
+macro_rules! no_padding {
+    ($($t:ty),*) => {
+        $(unsafe impl NoPadding for $t {})*
+    };
+}
+
+no_padding!(
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    bool,
+    char,
+    ()
+);
+
+unsafe impl<T: NoPadding, const N: usize> NoPadding for [T; N] {}
+
+impl<T: NoPadding> SeqLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            seq: AtomicUsize::new(0),
+            writer: SpinLock::new(()),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    //
+    // The acquire fence pairs with the release fence of the writer: if any of the bytes we copied was written after the
+    //  sequence number was made odd, the second load of the sequence number sees (at least) that odd number.
+    //
+    pub fn read(&self) -> T {
+        loop {
+            let seq = self.seq.load(Ordering::Acquire);
+            if seq & 1 == 1 {
+                core::hint::spin_loop();
+                continue;
+            }
+
+            let value = unsafe { atomic_load(self.value.get()) };
+
+            fence(Ordering::Acquire);
+
+            if self.seq.load(Ordering::Relaxed) == seq {
+                return unsafe { value.assume_init() };
+            }
+        }
+    }
+
+    pub fn write(&self, value: T) {
+        let _guard = self.writer.lock();
+
+        self.publish(value);
+    }
+
+    // Holding the writer lock, nobody else writes the value, so reading it back does not need the sequence number.
+    pub fn update<F>(&self, f: F)
+    where
+        F: FnOnce(T) -> T,
+    {
+        let _guard = self.writer.lock();
+
+        let value = f(unsafe { atomic_load(self.value.get()).assume_init() });
+        self.publish(value);
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+
+    //
+    // Must only be called with the writer lock held.
+    //
+    // The release fence orders the (odd) sequence number store before the stores of the value. The final release store
+    //  makes the complete value visible to readers that see the new (even) sequence number.
+    //
+    fn publish(&self, value: T) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+
+        fence(Ordering::Release);
+
+        unsafe { atomic_store(self.value.get(), value) };
+
+        self.seq.store(seq.wrapping_add(2), Ordering::Release);
+    }
+}
+
+/// # Safety
+/// 'src' must be valid for reads, and only ever be accessed atomically while others may access it concurrently.
+unsafe fn atomic_load<T: NoPadding>(src: *const T) -> MaybeUninit<T> {
+    let mut value = MaybeUninit::<T>::uninit();
+    let src = src.cast::<u8>().cast_mut();
+    let dst = value.as_mut_ptr().cast::<u8>();
+
+    for i in 0..mem::size_of::<T>() {
+        *dst.add(i) = AtomicU8::from_ptr(src.add(i)).load(Ordering::Relaxed);
+    }
+
+    value
+}
+
+/// # Safety
+/// 'dst' must be valid for writes, and only ever be accessed atomically while others may access it concurrently.
+unsafe fn atomic_store<T: NoPadding>(dst: *mut T, value: T) {
+    let src = (&value as *const T).cast::<u8>();
+    let dst = dst.cast::<u8>();
+
+    for i in 0..mem::size_of::<T>() {
+        AtomicU8::from_ptr(dst.add(i)).store(*src.add(i), Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_seqlock() {
+    const WRITES: u64 = 10_000;
+
+    // All elements are always written with the same number, so a torn read shows up as a mix of different numbers.
+    let l = SeqLock::new([0u64; 8]);
+
+    thread::scope(|s| {
+        for _ in 0..2 {
+            s.spawn(|| {
+                let mut last = 0;
+                while last != WRITES {
+                    let v = l.read();
+                    assert!(v.iter().all(|&n| n == v[0]), "torn read: {v:?}");
+                    assert!(v[0] >= last);
+                    last = v[0];
+                }
+            });
+        }
+
+        s.spawn(|| {
+            for i in 1..=WRITES / 2 {
+                l.write([i; 8]);
+            }
+        });
+
+        s.spawn(|| {
+            while l.read()[0] < WRITES / 2 {
+                thread::yield_now();
+            }
+
+            for _ in WRITES / 2..WRITES {
+                l.update(|v| v.map(|n| n + 1));
+            }
+        });
+    });
+
+    assert_eq!(l.into_inner(), [WRITES; 8]);
+}
+
+#[test]
+fn test_seqlock_struct() {
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct Point {
+        x: u32,
+        y: u32,
+    }
+
+    unsafe impl NoPadding for Point {}
+
+    let l = SeqLock::new(Point { x: 0, y: 0 });
+
+    thread::scope(|s| {
+        s.spawn(|| {
+            for i in 1..=1000 {
+                l.write(Point { x: i, y: i });
+            }
+        });
+
+        s.spawn(|| loop {
+            let p = l.read();
+            assert_eq!(p.x, p.y, "torn read: {p:?}");
+            if p.x == 1000 {
+                break;
+            }
+        });
+    });
+
+    l.update(|p| Point { y: p.y + 1, ..p });
+    assert_eq!(l.into_inner(), Point { x: 1000, y: 1001 });
+}
@@ -1,3 +1,8 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 pub mod ch04_spin_lock;
 pub mod ch05_channel;
 pub mod ch06_arc;
+pub mod ch10_ideas;